@@ -0,0 +1,184 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Files up to this size are embedded in the issue body in full.
+pub const INLINE_LIMIT: usize = 10_000;
+
+/// Largest file that can be attached at all, checked before it is read.
+pub const MAX_ATTACHMENT_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Number of trailing lines shown as a preview when a file goes to a gist.
+const PREVIEW_LINES: usize = 50;
+
+/// A text file to be embedded in, or linked from, the issue body.
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    /// The `file` field this attachment belongs to, or `None` for `--attach`.
+    pub field: Option<String>,
+    pub path: PathBuf,
+    pub content: String,
+    pub gist_url: Option<String>,
+}
+
+impl Attachment {
+    pub fn load(field: Option<&str>, path: &Path) -> Result<Self, String> {
+        let bytes = read_capped(path, MAX_ATTACHMENT_SIZE)?;
+        let content = String::from_utf8(bytes)
+            .map_err(|_| format!("{} is not a text file", path.display()))?;
+
+        Ok(Attachment {
+            field: field.map(|f| f.to_string()),
+            path: path.to_path_buf(),
+            content,
+            gist_url: None,
+        })
+    }
+
+    /// Whether the file is too large to embed and should be uploaded as a gist.
+    pub fn needs_gist(&self) -> bool {
+        self.content.len() > INLINE_LIMIT
+    }
+
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.display().to_string())
+    }
+
+    /// Render the attachment as Markdown: a fenced code block for small files,
+    /// or a gist link followed by the tail of the file for large ones.
+    pub fn render(&self) -> String {
+        let lang = self
+            .path
+            .extension()
+            .and_then(|e| e.to_str())
+            .filter(|e| e.chars().all(|c| c.is_ascii_alphanumeric()))
+            .unwrap_or("");
+
+        if !self.needs_gist() {
+            return fenced(&self.content, lang);
+        }
+
+        let lines: Vec<&str> = self.content.lines().collect();
+        let start = lines.len().saturating_sub(PREVIEW_LINES);
+        let preview = lines[start..].join("\n");
+
+        let link = match &self.gist_url {
+            Some(url) => format!("Full file: [{}]({})", self.file_name(), url),
+            None => format!(
+                "Full file: `{}` (will be uploaded as a gist)",
+                self.file_name()
+            ),
+        };
+
+        format!(
+            "{}\n\n_Truncated to the last {} of {} lines._\n\n{}",
+            link,
+            lines.len() - start,
+            lines.len(),
+            fenced(&preview, lang)
+        )
+    }
+}
+
+/// Read a file, refusing ones larger than `limit` bytes without loading them.
+fn read_capped(path: &Path, limit: u64) -> Result<Vec<u8>, String> {
+    let read_error = |e: std::io::Error| format!("Failed to read {}: {}", path.display(), e);
+    let too_large = || {
+        format!(
+            "{} is larger than the {} MB attachment limit",
+            path.display(),
+            limit / (1024 * 1024)
+        )
+    };
+
+    let file = std::fs::File::open(path).map_err(read_error)?;
+    if file.metadata().map_err(read_error)?.len() > limit {
+        return Err(too_large());
+    }
+    // The file may grow between the check and the read
+    let mut bytes = Vec::new();
+    file.take(limit + 1)
+        .read_to_end(&mut bytes)
+        .map_err(read_error)?;
+    if bytes.len() as u64 > limit {
+        return Err(too_large());
+    }
+    Ok(bytes)
+}
+
+/// Wrap text in a code fence longer than any backtick run it contains.
+pub fn fenced(content: &str, lang: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in content.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!(
+        "{}{}\n{}\n{}",
+        fence,
+        lang,
+        content.trim_end_matches('\n'),
+        fence
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachment(name: &str, content: String) -> Attachment {
+        Attachment {
+            field: None,
+            path: PathBuf::from(name),
+            content,
+            gist_url: None,
+        }
+    }
+
+    #[test]
+    fn test_read_capped() {
+        let path = std::env::temp_dir().join(format!("gripe-attach-{}.txt", std::process::id()));
+        std::fs::write(&path, "x".repeat(100)).unwrap();
+        let result = read_capped(&path, 50);
+        let small = read_capped(&path, 100);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.unwrap_err().contains("attachment limit"));
+        assert_eq!(small.unwrap().len(), 100);
+    }
+
+    #[test]
+    fn test_render_small_file_inline() {
+        let a = attachment("app.log", "line one\nline two\n".to_string());
+        assert_eq!(a.render(), "```log\nline one\nline two\n```");
+    }
+
+    #[test]
+    fn test_fence_longer_than_content_backticks() {
+        let a = attachment("notes.md", "```rust\nfn main() {}\n```".to_string());
+        assert!(a.render().starts_with("````md\n"));
+        assert!(a.render().ends_with("\n````"));
+    }
+
+    #[test]
+    fn test_render_large_file_links_gist_and_truncates() {
+        let content: String = (0..2000).map(|i| format!("line {}\n", i)).collect();
+        let mut a = attachment("big.log", content);
+        a.gist_url = Some("https://gist.github.com/abc".to_string());
+
+        let rendered = a.render();
+        assert!(a.needs_gist());
+        assert!(rendered.starts_with("Full file: [big.log](https://gist.github.com/abc)"));
+        assert!(rendered.contains("last 50 of 2000 lines"));
+        assert!(rendered.contains("line 1999"));
+        assert!(!rendered.contains("line 1949\n"));
+    }
+}
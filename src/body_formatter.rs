@@ -1,10 +1,112 @@
 use std::collections::HashMap;
 
-use crate::types::GripeSchema;
+use crate::attachments::Attachment;
+use crate::template::{Context, Template, Var};
+use crate::title_template::TitleTemplate;
+use crate::types::{ExtraFields, FieldType, GripeSchema};
+
+/// GitHub rejects issue and comment bodies longer than this many characters.
+pub const MAX_BODY_CHARS: usize = 65_536;
+
+/// Room left in each overflow comment for its heading.
+const COMMENT_HEADER_RESERVE: usize = 256;
+
+/// Room left in the issue body for the [`marker`].
+const MARKER_RESERVE: usize = 256;
+
+/// The full value of a textarea field that was truncated to fit the body.
+#[derive(Debug, Clone)]
+pub struct Overflow {
+    pub label: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct FormattedBody {
+    pub text: String,
+    /// Fields that were shortened; their full content should be posted
+    /// as follow-up comments.
+    pub overflow: Vec<Overflow>,
+}
 
 /// Render field values into a Markdown issue body.
-pub fn format_body(schema: &GripeSchema, values: &HashMap<String, String>) -> String {
-    let mut sections = Vec::new();
+///
+/// `file` fields are rendered from their matching attachment; attachments not
+/// tied to a field (from `--attach`) are collected under an Attachments heading.
+/// When the schema has a `body_template`, fields are laid out by the template
+/// instead of as `### Label` sections.
+///
+/// If the result exceeds GitHub's body limit, textarea fields are truncated to
+/// the largest common length that fits, each with a notice.
+pub fn format_body(
+    schema: &GripeSchema,
+    values: &HashMap<String, String>,
+    attachments: &[Attachment],
+) -> Result<FormattedBody, String> {
+    let template = match &schema.body_template {
+        Some(t) => Some(Template::parse(&t.source()?)?),
+        None => None,
+    };
+    let template = template.as_ref();
+
+    let limit = MAX_BODY_CHARS - MARKER_RESERVE;
+    let text = render_body(schema, template, values, attachments, None);
+    if text.chars().count() <= limit {
+        return Ok(FormattedBody {
+            text,
+            overflow: Vec::new(),
+        });
+    }
+
+    let textareas: Vec<_> = schema
+        .fields
+        .iter()
+        .filter(|f| f.field_type == FieldType::Textarea)
+        .filter_map(|f| values.get(&f.id).map(|v| (f, v)))
+        .collect();
+
+    // Binary search the largest per-field cap that still fits
+    let mut lo = 0;
+    let mut hi = textareas
+        .iter()
+        .map(|(_, v)| v.chars().count())
+        .max()
+        .unwrap_or(0);
+    while lo < hi {
+        let mid = (lo + hi).div_ceil(2);
+        let len = render_body(schema, template, values, attachments, Some(mid))
+            .chars()
+            .count();
+        if len <= limit {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+
+    let overflow = textareas
+        .iter()
+        .filter(|(_, v)| v.chars().count() > lo)
+        .map(|(f, v)| Overflow {
+            label: f.display_label().to_string(),
+            content: v.to_string(),
+        })
+        .collect();
+
+    Ok(FormattedBody {
+        text: render_body(schema, template, values, attachments, Some(lo)),
+        overflow,
+    })
+}
+
+fn render_body(
+    schema: &GripeSchema,
+    template: Option<&Template>,
+    values: &HashMap<String, String>,
+    attachments: &[Attachment],
+    textarea_cap: Option<usize>,
+) -> String {
+    let mut fields = Vec::new();
 
     for field in &schema.fields {
         let value = values.get(&field.id).map(|s| s.as_str()).unwrap_or("");
@@ -12,35 +114,300 @@ pub fn format_body(schema: &GripeSchema, values: &HashMap<String, String>) -> St
             continue;
         }
 
-        let label = field.display_label();
-        sections.push(format!("### {}\n\n{}", label, value));
+        let rendered = match field.field_type {
+            FieldType::File => attachments
+                .iter()
+                .find(|a| a.field.as_deref() == Some(field.id.as_str()))
+                .map(|a| a.render())
+                .unwrap_or_else(|| value.to_string()),
+            FieldType::Textarea => match textarea_cap {
+                Some(cap) => truncate_with_notice(value, cap),
+                None => value.to_string(),
+            },
+            // Empty when unchecked, so `{{#if id}}` works in body templates
+            FieldType::Checkbox if value == "true" => value.to_string(),
+            FieldType::Checkbox => String::new(),
+            _ => value.to_string(),
+        };
+        fields.push((field, rendered));
+    }
+
+    let extra = attachments
+        .iter()
+        .filter(|a| a.field.is_none())
+        .map(|a| format!("**{}**\n\n{}", a.file_name(), a.render()))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let other = match schema.extra_fields {
+        ExtraFields::Append => schema
+            .extra_keys(values)
+            .into_iter()
+            .filter(|k| !values[*k].is_empty())
+            .map(|k| format!("**{}**\n\n{}", k, values[k]))
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        ExtraFields::Reject | ExtraFields::Ignore => String::new(),
+    };
+
+    if let Some(template) = template {
+        let mut ctx: Context = fields
+            .into_iter()
+            .map(|(field, value)| {
+                let var = Var {
+                    label: field.display_label().to_string(),
+                    value,
+                };
+                (field.id.clone(), var)
+            })
+            .collect();
+        ctx.entry("attachments".to_string()).or_insert(Var {
+            label: "Attachments".to_string(),
+            value: extra,
+        });
+        ctx.entry("other".to_string()).or_insert(Var {
+            label: "Other".to_string(),
+            value: other,
+        });
+        return template.render(&ctx);
+    }
+
+    let mut sections: Vec<String> = fields
+        .into_iter()
+        .map(|(field, value)| match field.field_type {
+            // The checkbox line carries the label itself
+            FieldType::Checkbox => {
+                let mark = if value.is_empty() { " " } else { "x" };
+                format!("- [{}] {}", mark, field.display_label())
+            }
+            FieldType::List => {
+                let items: Vec<String> = value.lines().map(|item| format!("- {}", item)).collect();
+                format!("### {}\n\n{}", field.display_label(), items.join("\n"))
+            }
+            _ => format!("### {}\n\n{}", field.display_label(), value),
+        })
+        .collect();
+    if !other.is_empty() {
+        sections.push(format!("### Other\n\n{}", other));
+    }
+    if !extra.is_empty() {
+        sections.push(format!("### Attachments\n\n{}", extra));
     }
 
     sections.join("\n\n")
 }
 
-/// Render the issue title from the template.
-pub fn format_title(template: Option<&str>, values: &HashMap<String, String>) -> String {
-    match template {
-        Some(tmpl) => {
-            let mut title = tmpl.to_string();
-            for (key, val) in values {
-                title = title.replace(&format!("{{{}}}", key), val);
-            }
-            // Clean up any unreplaced placeholders
-            title = title.replace("{", "").replace("}", "");
-            title
+/// A hidden comment appended to issue bodies so `gripe list` can tell
+/// gripes apart from other issues, naming the schema if it has a name.
+pub fn marker(schema_name: Option<&str>) -> String {
+    match schema_name {
+        Some(name) => {
+            let name: String = name
+                .chars()
+                .filter(|c| !matches!(c, '"' | '<' | '>'))
+                .take(MARKER_RESERVE / 2)
+                .collect();
+            format!("<!-- gripe schema=\"{}\" -->", name)
         }
-        None => values
-            .get("summary")
-            .cloned()
-            .unwrap_or_else(|| "Feedback".to_string()),
+        None => "<!-- gripe -->".to_string(),
     }
 }
 
+/// Find the [`marker`] in an issue body: `None` if it isn't a gripe,
+/// otherwise the schema name, if any.
+pub fn parse_marker(body: &str) -> Option<Option<String>> {
+    let start = body.rfind("<!-- gripe")?;
+    let rest = &body[start + "<!-- gripe".len()..];
+    let end = rest.find("-->")?;
+    let inner = rest[..end].trim();
+    Some(
+        inner
+            .strip_prefix("schema=\"")
+            .and_then(|s| s.strip_suffix('"'))
+            .map(|s| s.to_string()),
+    )
+}
+
+fn truncate_with_notice(value: &str, cap: usize) -> String {
+    let total = value.chars().count();
+    if total <= cap {
+        return value.to_string();
+    }
+    let head: String = value.chars().take(cap).collect();
+    format!(
+        "{}\n\n_[Truncated: showing {} of {} characters. The full text is posted as a comment.]_",
+        head, cap, total
+    )
+}
+
+/// Split overflowed fields into comment bodies that each fit GitHub's limit.
+pub fn overflow_comments(overflow: &[Overflow]) -> Vec<String> {
+    let chunk_size = MAX_BODY_CHARS - COMMENT_HEADER_RESERVE;
+    let mut comments = Vec::new();
+
+    for item in overflow {
+        let chars: Vec<char> = item.content.chars().collect();
+        let parts: Vec<String> = chars
+            .chunks(chunk_size)
+            .map(|c| c.iter().collect())
+            .collect();
+        for (i, part) in parts.iter().enumerate() {
+            let heading = if parts.len() > 1 {
+                format!(
+                    "### {} (full text, part {}/{})",
+                    item.label,
+                    i + 1,
+                    parts.len()
+                )
+            } else {
+                format!("### {} (full text)", item.label)
+            };
+            comments.push(format!("{}\n\n{}", heading, part));
+        }
+    }
+
+    comments
+}
+
+/// Render the issue title from the template.
+pub fn format_title(
+    template: Option<&str>,
+    values: &HashMap<String, String>,
+) -> Result<String, String> {
+    let tmpl = template.unwrap_or("{summary|default:\"Feedback\"}");
+    let title = TitleTemplate::parse(tmpl)?.render(values);
+    if title.is_empty() {
+        return Ok("Feedback".to_string());
+    }
+    Ok(title)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::defaults::default_schema;
+    use crate::types::BodyTemplate;
+
+    #[test]
+    fn test_format_body_truncates_oversized_textareas() {
+        let schema = default_schema();
+        let mut values = HashMap::new();
+        values.insert("summary".to_string(), "huge logs".to_string());
+        values.insert("expected".to_string(), "x".repeat(50_000));
+        values.insert("actual".to_string(), "y".repeat(50_000));
+
+        let body = format_body(&schema, &values, &[]).unwrap();
+        assert!(body.text.chars().count() <= MAX_BODY_CHARS);
+        assert!(body.text.contains("_[Truncated: showing"));
+        assert!(body.text.contains("### Summary\n\nhuge logs"));
+        assert_eq!(body.overflow.len(), 2);
+        assert_eq!(body.overflow[0].content.len(), 50_000);
+    }
+
+    #[test]
+    fn test_format_body_small_is_untouched() {
+        let schema = default_schema();
+        let mut values = HashMap::new();
+        values.insert("actual".to_string(), "it crashed".to_string());
+
+        let body = format_body(&schema, &values, &[]).unwrap();
+        assert_eq!(body.text, "### Actual Behavior\n\nit crashed");
+        assert!(body.overflow.is_empty());
+    }
+
+    #[test]
+    fn test_format_body_with_template() {
+        let mut schema = default_schema();
+        schema.body_template = Some(BodyTemplate::Inline(
+            "**{{tool}}**: {{summary}}\n{{#if context}}\n{{context | details}}\n{{/if}}"
+                .to_string(),
+        ));
+        let mut values = HashMap::new();
+        values.insert("tool".to_string(), "vim".to_string());
+        values.insert("summary".to_string(), "crash".to_string());
+
+        let body = format_body(&schema, &values, &[]).unwrap();
+        assert_eq!(body.text, "**vim**: crash\n");
+
+        values.insert("context".to_string(), "on save".to_string());
+        let body = format_body(&schema, &values, &[]).unwrap();
+        assert!(body.text.contains("<summary>Additional Context</summary>"));
+    }
+
+    #[test]
+    fn test_extra_fields_append() {
+        let mut schema = default_schema();
+        let mut values = HashMap::new();
+        values.insert("actual".to_string(), "it crashed".to_string());
+        values.insert("os".to_string(), "linux".to_string());
+        values.insert("reporter".to_string(), "nightly-agent".to_string());
+
+        let body = format_body(&schema, &values, &[]).unwrap();
+        assert!(!body.text.contains("linux"));
+
+        schema.extra_fields = ExtraFields::Append;
+        let body = format_body(&schema, &values, &[]).unwrap();
+        assert!(body.text.ends_with("### Other\n\n**os**\n\nlinux"));
+    }
+
+    #[test]
+    fn test_lists_and_checkboxes() {
+        let schema: GripeSchema = serde_yaml::from_str(
+            "fields:\n  - { id: platforms, label: Platforms, type: list }\n  - { id: agree, label: I searched for duplicates, type: checkbox }\n",
+        )
+        .unwrap();
+        let mut values = HashMap::new();
+        values.insert("platforms".to_string(), "linux\nmac".to_string());
+        values.insert("agree".to_string(), "true".to_string());
+
+        let body = format_body(&schema, &values, &[]).unwrap();
+        assert_eq!(
+            body.text,
+            "### Platforms\n\n- linux\n- mac\n\n- [x] I searched for duplicates"
+        );
+    }
+
+    #[test]
+    fn test_checkboxes_in_templates() {
+        let mut schema: GripeSchema = serde_yaml::from_str(
+            "fields:\n  - { id: agree, label: I searched for duplicates, type: checkbox }\n",
+        )
+        .unwrap();
+        let mut values = HashMap::new();
+        values.insert("agree".to_string(), "false".to_string());
+        let body = format_body(&schema, &values, &[]).unwrap();
+        assert_eq!(body.text, "- [ ] I searched for duplicates");
+
+        schema.body_template = Some(BodyTemplate::Inline(
+            "{{#if agree}}searched{{else}}not searched{{/if}}".to_string(),
+        ));
+        let body = format_body(&schema, &values, &[]).unwrap();
+        assert_eq!(body.text, "not searched");
+
+        values.insert("agree".to_string(), "true".to_string());
+        let body = format_body(&schema, &values, &[]).unwrap();
+        assert_eq!(body.text, "searched");
+    }
+
+    #[test]
+    fn test_marker_round_trip() {
+        let body = format!("text\n\n{}", marker(Some("Bug \"report\"")));
+        assert_eq!(parse_marker(&body), Some(Some("Bug report".to_string())));
+        assert_eq!(parse_marker(&marker(None)), Some(None));
+        assert_eq!(parse_marker("no marker here"), None);
+    }
+
+    #[test]
+    fn test_overflow_comments_are_chunked() {
+        let overflow = vec![Overflow {
+            label: "Logs".to_string(),
+            content: "z".repeat(MAX_BODY_CHARS * 2),
+        }];
+        let comments = overflow_comments(&overflow);
+        assert_eq!(comments.len(), 3);
+        assert!(comments[0].starts_with("### Logs (full text, part 1/3)"));
+        assert!(comments.iter().all(|c| c.chars().count() <= MAX_BODY_CHARS));
+    }
 
     #[test]
     fn test_format_title_with_template() {
@@ -48,7 +415,7 @@ mod tests {
         values.insert("tool".to_string(), "vim".to_string());
         values.insert("summary".to_string(), "cursor jumps".to_string());
 
-        let title = format_title(Some("[{tool}] {summary}"), &values);
+        let title = format_title(Some("[{tool}] {summary}"), &values).unwrap();
         assert_eq!(title, "[vim] cursor jumps");
     }
 
@@ -57,7 +424,7 @@ mod tests {
         let mut values = HashMap::new();
         values.insert("summary".to_string(), "something broke".to_string());
 
-        let title = format_title(None, &values);
+        let title = format_title(None, &values).unwrap();
         assert_eq!(title, "something broke");
     }
 }
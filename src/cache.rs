@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// How long a cached response is used without revalidating.
+const DEFAULT_TTL_SECS: u64 = 3600;
+
+/// A contents API response as last fetched from GitHub.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CachedFile {
+    pub etag: Option<String>,
+    /// Raw response body, or `None` if the path doesn't exist.
+    pub content: Option<String>,
+    pub fetched_at: u64,
+}
+
+impl CachedFile {
+    pub fn new(etag: Option<String>, content: Option<String>) -> Self {
+        CachedFile {
+            etag,
+            content,
+            fetched_at: now(),
+        }
+    }
+
+    /// Whether the entry is young enough to use without asking GitHub.
+    /// The TTL can be overridden with `GRIPE_POLICY_TTL` (seconds).
+    pub fn is_fresh(&self) -> bool {
+        let ttl = std::env::var("GRIPE_POLICY_TTL")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TTL_SECS);
+        now().saturating_sub(self.fetched_at) < ttl
+    }
+
+    pub fn touch(&mut self) {
+        self.fetched_at = now();
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// One file per key, named by a hash of the whole key so that distinct keys
+/// never share a file.
+fn cache_path(key: &str) -> Option<PathBuf> {
+    Some(
+        dirs::cache_dir()?
+            .join("gripe")
+            .join("remote")
+            .join(format!("{:016x}.json", fnv1a(key))),
+    )
+}
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher` its output is fixed, so cache file
+/// names stay the same across Rust versions.
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+pub fn load(key: &str) -> Option<CachedFile> {
+    let contents = std::fs::read_to_string(cache_path(key)?).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Save an entry. Failures are ignored; the cache is only an optimization.
+pub fn store(key: &str, entry: &CachedFile) {
+    let Some(path) = cache_path(key) else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Ok(json) = serde_json::to_string(entry) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_freshness() {
+        let mut entry = CachedFile::new(Some("\"abc\"".to_string()), None);
+        assert!(entry.is_fresh());
+        entry.fetched_at -= DEFAULT_TTL_SECS + 1;
+        assert!(!entry.is_fresh());
+        entry.touch();
+        assert!(entry.is_fresh());
+    }
+
+    #[test]
+    fn test_cache_paths_are_distinct() {
+        let path = cache_path("github.com/repos/a_b/c/contents/gripe.yaml").unwrap();
+        assert!(path.parent().unwrap().ends_with("gripe/remote"));
+        assert_ne!(
+            path,
+            cache_path("github.com/repos/a/b_c/contents/gripe.yaml").unwrap()
+        );
+        assert_ne!(
+            path,
+            cache_path("ghe.example.com/repos/a_b/c/contents/gripe.yaml").unwrap()
+        );
+        assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::error::GripeError;
+use crate::gh;
+use crate::git::find_git_root;
+use crate::types::CodeOwnersConfig;
+
+/// Where GitHub looks for a CODEOWNERS file, in order.
+const LOCATIONS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// A parsed CODEOWNERS file.
+pub struct CodeOwners {
+    rules: Vec<(Regex, Vec<String>)>,
+}
+
+impl CodeOwners {
+    /// Parse CODEOWNERS contents. Lines with patterns gripe can't translate
+    /// are skipped rather than failing the submission.
+    pub fn parse(contents: &str) -> Self {
+        let rules = contents
+            .lines()
+            .map(|l| l.split('#').next().unwrap_or("").trim())
+            .filter(|l| !l.is_empty())
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let pattern = pattern_regex(parts.next()?)?;
+                Some((pattern, parts.map(|o| o.to_string()).collect()))
+            })
+            .collect();
+        CodeOwners { rules }
+    }
+
+    /// Owners of `path`. As on GitHub, the last matching line wins, and a
+    /// matching line with no owners means the path has none.
+    pub fn owners(&self, path: &str) -> &[String] {
+        let path = path.trim_start_matches("./").trim_matches('/');
+        self.rules
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.is_match(path))
+            .map(|(_, owners)| owners.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+/// Translate a CODEOWNERS (gitignore-style) pattern into a regex over
+/// repo-relative paths.
+fn pattern_regex(pattern: &str) -> Option<Regex> {
+    let trimmed = pattern.trim_end_matches('/');
+    // A slash anywhere but the end anchors the pattern to the repo root
+    let anchored = trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+
+    let mut re = String::from("^");
+    if !anchored {
+        re.push_str("(?:.*/)?");
+    }
+    let mut chars = trimmed.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    re.push_str("(?:.*/)?");
+                } else {
+                    re.push_str(".*");
+                }
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    // `docs/*` covers files directly in docs; anything else also covers
+    // everything beneath a matching directory
+    if !trimmed.ends_with("/*") {
+        re.push_str("(?:/.*)?");
+    }
+    re.push('$');
+
+    Regex::new(&re).ok()
+}
+
+/// Read the CODEOWNERS file for the submission: the target repo's when
+/// filing with its published schema, otherwise the local checkout's.
+pub fn load(remote_repo: Option<&str>, refresh: bool) -> Result<Option<String>, GripeError> {
+    if let Some(repo) = remote_repo {
+        for location in LOCATIONS {
+            if let Some(contents) = gh::fetch_remote_file(repo, location, refresh)? {
+                return Ok(Some(contents));
+            }
+        }
+        return Ok(None);
+    }
+
+    let cwd =
+        std::env::current_dir().map_err(|e| GripeError::Other(format!("Cannot get cwd: {}", e)))?;
+    let Some(root) = find_git_root(&cwd) else {
+        return Ok(None);
+    };
+    Ok(LOCATIONS
+        .iter()
+        .find_map(|location| std::fs::read_to_string(root.join(Path::new(location))).ok()))
+}
+
+/// Users who own the path named by the configured field. Team and email
+/// owners can't be assigned to issues, so they're left out.
+pub fn assignees(
+    config: &CodeOwnersConfig,
+    values: &HashMap<String, String>,
+    codeowners: &CodeOwners,
+) -> Vec<String> {
+    let Some(value) = values
+        .get(&config.field)
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+    else {
+        return Vec::new();
+    };
+    let path = match &config.path {
+        Some(template) => template.replace("{value}", value),
+        None => value.to_string(),
+    };
+
+    codeowners
+        .owners(&path)
+        .iter()
+        .filter_map(|owner| owner.strip_prefix('@'))
+        .filter(|login| !login.contains('/'))
+        .map(|login| login.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = "\
+* @default-owner
+*.js @js-owner # frontend
+/build/logs/ @doctocat
+docs/* docs@example.com
+apps/ @octocat
+/src/parser/ @parser-owner @acme/parsers
+/src/parser/generated/
+";
+
+    #[test]
+    fn test_last_match_wins() {
+        let owners = CodeOwners::parse(FILE);
+        assert_eq!(owners.owners("README.md"), ["@default-owner"]);
+        assert_eq!(owners.owners("web/app.js"), ["@js-owner"]);
+        assert_eq!(owners.owners("build/logs/today.txt"), ["@doctocat"]);
+        assert_eq!(owners.owners("src/parser/lexer.rs").len(), 2);
+        assert!(owners.owners("src/parser/generated/x.rs").is_empty());
+    }
+
+    #[test]
+    fn test_directory_patterns() {
+        let owners = CodeOwners::parse(FILE);
+        assert_eq!(owners.owners("docs/intro.md"), ["docs@example.com"]);
+        assert_eq!(owners.owners("docs/guide/intro.md"), ["@default-owner"]);
+        assert_eq!(owners.owners("nested/apps/main.rs"), ["@octocat"]);
+        assert_eq!(owners.owners("./src/parser/"), owners.owners("src/parser"));
+    }
+
+    #[test]
+    fn test_assignees_skip_teams_and_emails() {
+        let owners = CodeOwners::parse(FILE);
+        let config = CodeOwnersConfig {
+            field: "component".to_string(),
+            path: Some("src/{value}/".to_string()),
+        };
+        let values = HashMap::from([("component".to_string(), "parser".to_string())]);
+        assert_eq!(assignees(&config, &values, &owners), ["parser-owner"]);
+        let values = HashMap::from([("component".to_string(), String::new())]);
+        assert!(assignees(&config, &values, &owners).is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+use clap::Args;
+use colored::Colorize;
+use dialoguer::Input;
+
+use crate::body_formatter::{format_body, overflow_comments, Overflow, MAX_BODY_CHARS};
+use crate::commands::submit::{
+    check_extra_fields, interactive_prompt, load_attachments, parse_json_input, parse_kv_args,
+    redact_inputs, validate_fields,
+};
+use crate::config::resolve_schema;
+use crate::error::GripeError;
+use crate::gh;
+use crate::redact::Redactor;
+use crate::types::GripeSchema;
+use crate::values;
+
+#[derive(Args)]
+pub struct CommentArgs {
+    /// Issue number, owner/repo#number or issue URL
+    issue: String,
+    /// Comment text, for schemas without comment_fields
+    #[arg(long, conflicts_with_all = ["json", "stdin"])]
+    body: Option<String>,
+    /// JSON string with comment field values
+    #[arg(long)]
+    json: Option<String>,
+    /// Read JSON (or, without comment_fields, the comment text) from stdin
+    #[arg(long)]
+    stdin: bool,
+    /// Repository of the issue, when giving just its number
+    #[arg(long)]
+    repo: Option<String>,
+    /// Preview without posting the comment
+    #[arg(long)]
+    dry_run: bool,
+    /// Output result as JSON
+    #[arg(long)]
+    pub output_json: bool,
+    /// Comment field values as key=value pairs, or the comment text
+    #[arg(trailing_var_arg = true)]
+    fields: Vec<String>,
+}
+
+/// Add a comment to an existing issue, collected with the schema's
+/// `comment_fields` when it has them and as free text otherwise.
+pub fn run(args: CommentArgs) -> Result<(), GripeError> {
+    let schema = resolve_schema()?;
+    let (repo, number) =
+        gh::parse_issue_ref(&args.issue, args.repo.as_deref().or(schema.repo.as_deref()))?;
+    let redactor = Redactor::new(&schema.redact).map_err(GripeError::ConfigParse)?;
+
+    let (text, overflow) = if schema.comment_fields.is_empty() {
+        (free_text(&args, &redactor)?, Vec::new())
+    } else {
+        structured_body(&args, &schema, &redactor)?
+    };
+    if text.trim().is_empty() {
+        return Err(GripeError::Validation("The comment is empty.".to_string()));
+    }
+    if text.chars().count() > MAX_BODY_CHARS {
+        return Err(GripeError::Validation(format!(
+            "Comment is {} characters; GitHub allows at most {}.",
+            text.chars().count(),
+            MAX_BODY_CHARS
+        )));
+    }
+
+    if args.dry_run {
+        let truncated: Vec<&str> = overflow.iter().map(|o| o.label.as_str()).collect();
+        if args.output_json {
+            let output = serde_json::json!({
+                "repo": repo,
+                "number": number,
+                "body": text,
+                "truncated": truncated,
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&output)
+                    .map_err(|e| GripeError::Other(format!("JSON error: {}", e)))?
+            );
+        } else {
+            println!("{}", "--- Dry Run ---".yellow().bold());
+            println!("{}: {}#{}", "Issue".bold(), repo, number);
+            if !truncated.is_empty() {
+                println!(
+                    "{}: {} (full text will be posted as comments)",
+                    "Truncated".bold(),
+                    truncated.join(", ")
+                );
+            }
+            println!();
+            println!("{}", text);
+        }
+        return Ok(());
+    }
+
+    gh::check_gh_available()?;
+    let url = gh::add_comment(&repo, number, &text)?;
+    // The comment is posted, so report a failed overflow comment next to it
+    // instead of failing in a way that invites posting it again
+    let mut warnings = Vec::new();
+    for comment in overflow_comments(&overflow) {
+        if let Err(e) = gh::add_comment(&repo, number, &comment) {
+            warnings.push(format!("Truncated fields were not posted in full: {}", e));
+            break;
+        }
+    }
+    for warning in &warnings {
+        eprintln!("{} {}", "warning:".yellow(), warning);
+    }
+
+    if args.output_json {
+        let output = serde_json::json!({
+            "url": url,
+            "repo": repo,
+            "number": number,
+            "warnings": warnings,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output)
+                .map_err(|e| GripeError::Other(format!("JSON error: {}", e)))?
+        );
+    } else {
+        println!("{} Comment posted: {}", "✓".green(), url.underline());
+    }
+
+    Ok(())
+}
+
+/// The comment text from `--body`, stdin, trailing arguments or a prompt.
+fn free_text(args: &CommentArgs, redactor: &Redactor) -> Result<String, GripeError> {
+    if args.json.is_some() {
+        return Err(GripeError::Validation(
+            "This schema has no comment_fields; pass the comment with --body instead of --json."
+                .to_string(),
+        ));
+    }
+
+    let text = if let Some(body) = &args.body {
+        body.clone()
+    } else if args.stdin {
+        let mut buf = String::new();
+        io::stdin()
+            .read_to_string(&mut buf)
+            .map_err(|e| GripeError::Other(format!("Failed to read stdin: {}", e)))?;
+        buf.trim().to_string()
+    } else if !args.fields.is_empty() {
+        args.fields.join(" ")
+    } else {
+        Input::<String>::new()
+            .with_prompt("Comment")
+            .interact_text()
+            .map_err(|e| GripeError::Other(format!("Prompt error: {}", e)))?
+    };
+
+    let values = HashMap::from([("comment".to_string(), text)]);
+    let mut values = redact_inputs(redactor, values, &mut []);
+    Ok(values.remove("comment").unwrap_or_default())
+}
+
+/// Collect `comment_fields` values and render them like an issue body.
+fn structured_body(
+    args: &CommentArgs,
+    schema: &GripeSchema,
+    redactor: &Redactor,
+) -> Result<(String, Vec<Overflow>), GripeError> {
+    // Comments are laid out as sections; the issue's body_template doesn't apply
+    let comment_schema = GripeSchema {
+        fields: schema.comment_fields.clone(),
+        body_template: None,
+        ..schema.clone()
+    };
+
+    let values = if let Some(j) = &args.json {
+        parse_json_input(j)?
+    } else if args.stdin {
+        let mut buf = String::new();
+        io::stdin()
+            .read_to_string(&mut buf)
+            .map_err(|e| GripeError::Other(format!("Failed to read stdin: {}", e)))?;
+        parse_json_input(buf.trim())?
+    } else if !args.fields.is_empty() {
+        parse_kv_args(&args.fields)?
+    } else if args.body.is_some() {
+        return Err(GripeError::Validation(
+            "This schema has comment_fields; pass them as key=value pairs or --json instead of --body."
+                .to_string(),
+        ));
+    } else {
+        interactive_prompt(&comment_schema)?
+    };
+    let values = values::normalize(&comment_schema, values).map_err(GripeError::Validation)?;
+    validate_fields(&comment_schema, &values)?;
+    check_extra_fields(&comment_schema, &values)?;
+
+    let mut attachments = load_attachments(&comment_schema, &values, &[])?;
+    let values = redact_inputs(redactor, values, &mut attachments);
+    if !args.dry_run {
+        for attachment in attachments.iter_mut().filter(|a| a.needs_gist()) {
+            let desc = format!(
+                "{} (attachment for: {})",
+                attachment.file_name(),
+                args.issue
+            );
+            attachment.gist_url = Some(gh::create_gist(
+                &attachment.file_name(),
+                &attachment.content,
+                &desc,
+            )?);
+        }
+    }
+
+    let body =
+        format_body(&comment_schema, &values, &attachments).map_err(GripeError::ConfigParse)?;
+    Ok((body.text, body.overflow))
+}
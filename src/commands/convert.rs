@@ -0,0 +1,210 @@
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use colored::Colorize;
+
+use crate::config::{resolve_schema_with_source, schema_to_yaml};
+use crate::error::GripeError;
+use crate::git::find_git_root;
+use crate::github_templates::{parse_github_template, to_github_form};
+use crate::types::{FieldType, GripeSchema};
+
+#[derive(Args)]
+#[command(group = clap::ArgGroup::new("direction").required(true))]
+pub struct ConvertArgs {
+    /// Write the resolved schema as a GitHub issue form
+    #[arg(long, group = "direction", value_parser = ["github-form"])]
+    to: Option<String>,
+    /// Generate gripe.yaml from the GitHub issue form at INPUT
+    #[arg(long, group = "direction", value_parser = ["github-form"], requires = "input")]
+    from: Option<String>,
+    /// Issue form to read with --from
+    input: Option<PathBuf>,
+    /// Where to write (`-` for stdout); defaults to .github/ISSUE_TEMPLATE/<name>.yml
+    /// or ./gripe.yaml
+    #[arg(long, short)]
+    output: Option<String>,
+    /// Overwrite an existing file
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn run(args: ConvertArgs) -> Result<(), GripeError> {
+    match &args.input {
+        Some(input) if args.from.is_some() => from_github_form(input, &args),
+        _ => to_github_form_file(&args),
+    }
+}
+
+fn to_github_form_file(args: &ConvertArgs) -> Result<(), GripeError> {
+    let (schema, source) = resolve_schema_with_source()?;
+
+    let dropped = dropped_keys(&schema)?;
+    if !dropped.is_empty() {
+        eprintln!(
+            "{} Issue forms have no equivalent for {}; the web form will ignore them",
+            "warning:".yellow(),
+            dropped.join(", ")
+        );
+    }
+    if schema.title_template.is_some() {
+        eprintln!(
+            "{} Issue forms only prefill the literal start of title_template; the placeholders are left out",
+            "warning:".yellow()
+        );
+    }
+    for field in &schema.fields {
+        let lost: Vec<&str> = [
+            ("pattern", field.pattern.is_some()),
+            ("min", field.min.is_some()),
+            ("max", field.max.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(key, set)| set.then_some(key))
+        .collect();
+        if !lost.is_empty() {
+            eprintln!(
+                "{} Issue forms can't express {} on field '{}'; the web form won't check it",
+                "warning:".yellow(),
+                lost.join("/"),
+                field.id
+            );
+        }
+        if field.field_type == FieldType::File {
+            eprintln!(
+                "{} Field '{}' becomes a text area; the web form can't attach files by path",
+                "warning:".yellow(),
+                field.id
+            );
+        }
+    }
+
+    let form = to_github_form(&schema)
+        .map_err(|e| GripeError::Other(format!("Failed to serialize issue form: {}", e)))?;
+    let output = match &args.output {
+        Some(output) => output.clone(),
+        None => {
+            let cwd = std::env::current_dir()
+                .map_err(|e| GripeError::Other(format!("Cannot get cwd: {}", e)))?;
+            let root = find_git_root(&cwd).unwrap_or(cwd);
+            let name = slug(schema.name.as_deref().unwrap_or("feedback"));
+            root.join(".github")
+                .join("ISSUE_TEMPLATE")
+                .join(format!("{}.yml", name))
+                .display()
+                .to_string()
+        }
+    };
+
+    write_output(&output, &form, args.force)?;
+    if output != "-" {
+        println!(
+            "{} Wrote issue form {} from {}",
+            "✓".green(),
+            output,
+            source
+        );
+    }
+    Ok(())
+}
+
+/// Keys an issue form carries over; gripe.yaml's other settings are lost.
+const FORM_KEYS: &[&str] = &[
+    "name",
+    "repo",
+    "labels",
+    "assignees",
+    "projects",
+    "type",
+    "title_template",
+    "fields",
+];
+
+/// Top-level keys of `schema`, left at anything but their default, that
+/// the issue form drops.
+fn dropped_keys(schema: &GripeSchema) -> Result<Vec<String>, GripeError> {
+    let yaml = schema_to_yaml(schema)?;
+    let value: serde_yaml::Value = serde_yaml::from_str(&yaml)
+        .map_err(|e| GripeError::Other(format!("Failed to reread schema: {}", e)))?;
+    Ok(value
+        .as_mapping()
+        .into_iter()
+        .flat_map(|map| map.keys())
+        .filter_map(|key| key.as_str())
+        .filter(|key| !FORM_KEYS.contains(key))
+        .map(|key| key.to_string())
+        .collect())
+}
+
+fn from_github_form(input: &Path, args: &ConvertArgs) -> Result<(), GripeError> {
+    let contents = std::fs::read_to_string(input)
+        .map_err(|e| GripeError::NotFound(format!("Failed to read {}: {}", input.display(), e)))?;
+    let schema = parse_github_template(&contents).ok_or_else(|| {
+        GripeError::ConfigParse(format!(
+            "{} is not an issue form with any input fields",
+            input.display()
+        ))
+    })?;
+
+    let yaml = schema_to_yaml(&schema)?;
+    let output = args.output.as_deref().unwrap_or("gripe.yaml");
+    write_output(output, &yaml, args.force)?;
+    if output != "-" {
+        println!("{} Wrote {} from {}", "✓".green(), output, input.display());
+    }
+    Ok(())
+}
+
+fn write_output(output: &str, contents: &str, force: bool) -> Result<(), GripeError> {
+    if output == "-" {
+        print!("{}", contents);
+        return Ok(());
+    }
+
+    let path = Path::new(output);
+    if path.exists() && !force {
+        return Err(GripeError::Validation(format!(
+            "{} already exists. Use --force to overwrite.",
+            output
+        )));
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            GripeError::Other(format!("Failed to create {}: {}", parent.display(), e))
+        })?;
+    }
+    std::fs::write(path, contents)
+        .map_err(|e| GripeError::Other(format!("Failed to write {}: {}", output, e)))
+}
+
+/// File name for a template called `name`: lowercase words joined by `-`.
+fn slug(name: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "feedback".to_string()
+    } else {
+        slug
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dropped_keys() {
+        let schema: GripeSchema = serde_yaml::from_str(
+            "name: Bug\nlabels: [bug]\nmilestone: v1\nautomated: deny\nextra_fields: ignore\nrouting:\n  - { when: { area: ui }, labels: [ui] }\nfields: [{ id: area }]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            dropped_keys(&schema).unwrap(),
+            vec!["automated", "milestone", "routing"]
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use colored::Colorize;
+
+use crate::error::GripeError;
+use crate::history;
+
+/// Show recorded submissions, newest first.
+pub fn run(json: bool, repo: Option<String>, limit: usize) -> Result<(), GripeError> {
+    let entries: Vec<_> = history::load()?
+        .into_iter()
+        .rev()
+        .filter(|e| repo.as_ref().is_none_or(|r| &e.repo == r))
+        .take(limit)
+        .collect();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&entries)
+                .map_err(|e| GripeError::Other(format!("JSON error: {}", e)))?
+        );
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("No submissions recorded.");
+        return Ok(());
+    }
+
+    let width = entries
+        .iter()
+        .map(|e| e.repo.len() + e.number.to_string().len() + 1)
+        .max()
+        .unwrap_or(0);
+    for entry in &entries {
+        let id = format!("{:<width$}", format!("{}#{}", entry.repo, entry.number));
+        let date = entry.timestamp.get(..10).unwrap_or(&entry.timestamp);
+        let kind = if entry.kind == "issue" {
+            String::new()
+        } else {
+            format!(" ({})", entry.kind)
+        };
+        println!(
+            "{}  {}  {}{}",
+            date.dimmed(),
+            id.cyan(),
+            entry.title,
+            kind.dimmed()
+        );
+    }
+
+    Ok(())
+}
@@ -1,24 +1,359 @@
-use std::path::Path;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
+use clap::Args;
 use colored::Colorize;
+use dialoguer::{Confirm, Input, MultiSelect, Select};
 
-use crate::defaults::default_schema;
+use crate::config::schema_to_yaml;
+use crate::defaults::{default_schema, preset, PRESETS};
+use crate::error::GripeError;
+use crate::git::{detect_repo, find_git_root};
+use crate::github_templates::{is_template_file, parse_github_template};
+use crate::lint::lint;
+use crate::types::{AutomatedMode, FieldDefinition, FieldType, GripeSchema, ReviewTarget};
 
-pub fn run(force: bool) -> Result<(), String> {
+const FIELD_TYPES: &[&str] = &[
+    "input", "textarea", "select", "number", "checkbox", "list", "file",
+];
+
+#[derive(Args)]
+pub struct InitArgs {
+    /// Overwrite existing gripe.yaml
+    #[arg(long)]
+    force: bool,
+    /// Start from a built-in schema instead of asking
+    #[arg(long, value_parser = PRESETS.to_vec(), conflicts_with = "from_template")]
+    preset: Option<String>,
+    /// Generate the schema from a GitHub issue form instead of asking
+    #[arg(long, value_name = "PATH")]
+    from_template: Option<PathBuf>,
+}
+
+pub fn run(args: InitArgs) -> Result<(), GripeError> {
     let path = Path::new("gripe.yaml");
 
-    if path.exists() && !force {
-        return Err("gripe.yaml already exists. Use --force to overwrite.".to_string());
+    if path.exists() && !args.force {
+        return Err(GripeError::Validation(
+            "gripe.yaml already exists. Use --force to overwrite.".to_string(),
+        ));
     }
 
-    let schema = default_schema();
-    let yaml =
-        serde_yaml::to_string(&schema).map_err(|e| format!("Failed to serialize schema: {}", e))?;
+    let schema = if let Some(template) = &args.from_template {
+        load_template(template)?
+    } else if let Some(name) = &args.preset {
+        preset(name).ok_or_else(|| GripeError::Validation(format!("Unknown preset '{}'", name)))?
+    } else if std::io::stdin().is_terminal() {
+        wizard()?
+    } else {
+        default_schema()
+    };
+    let yaml = schema_to_yaml(&schema)?;
 
-    std::fs::write(path, &yaml).map_err(|e| format!("Failed to write gripe.yaml: {}", e))?;
+    std::fs::write(path, &yaml)
+        .map_err(|e| GripeError::Other(format!("Failed to write gripe.yaml: {}", e)))?;
 
     println!("{} Created gripe.yaml", "✓".green());
+    // Fields removed in the wizard may still be named by the title template
+    for d in lint(&yaml) {
+        eprintln!(
+            "{} gripe.yaml:{}: {}",
+            "warning:".yellow(),
+            d.line,
+            d.message
+        );
+    }
     println!("Edit the file to customize your feedback schema.");
 
     Ok(())
 }
+
+fn load_template(path: &Path) -> Result<GripeSchema, GripeError> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| GripeError::NotFound(format!("Failed to read {}: {}", path.display(), e)))?;
+    parse_github_template(&contents).ok_or_else(|| {
+        GripeError::ConfigParse(format!(
+            "{} is not an issue form with any input fields",
+            path.display()
+        ))
+    })
+}
+
+/// Issue forms in the repo's .github/ISSUE_TEMPLATE that gripe can import.
+fn existing_forms(root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(root.join(".github").join("ISSUE_TEMPLATE")) else {
+        return Vec::new();
+    };
+    let mut forms: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| is_template_file(&p.to_string_lossy()))
+        .filter(|p| load_template(p).is_ok())
+        .collect();
+    forms.sort();
+    forms
+}
+
+fn wizard() -> Result<GripeSchema, GripeError> {
+    let cwd =
+        std::env::current_dir().map_err(|e| GripeError::Other(format!("Cannot get cwd: {}", e)))?;
+    let root = find_git_root(&cwd).unwrap_or_else(|| cwd.clone());
+
+    // Start from an existing issue form, a preset or the defaults
+    let forms = existing_forms(&root);
+    let mut starts: Vec<String> = forms
+        .iter()
+        .map(|f| format!("Import {}", f.strip_prefix(&root).unwrap_or(f).display()))
+        .collect();
+    starts.extend(PRESETS.iter().map(|p| format!("{} preset", p)));
+    starts.push("Default fields".to_string());
+    let start = Select::new()
+        .with_prompt("Start from")
+        .items(&starts)
+        .default(0)
+        .interact()
+        .map_err(prompt_error)?;
+    let mut schema = match start {
+        i if i < forms.len() => load_template(&forms[i])?,
+        i if i < forms.len() + PRESETS.len() => {
+            preset(PRESETS[i - forms.len()]).unwrap_or_else(default_schema)
+        }
+        _ => default_schema(),
+    };
+
+    let repo: String = Input::new()
+        .with_prompt("Repository (owner/repo, empty to use the git remote)")
+        .default(
+            schema
+                .repo
+                .clone()
+                .or_else(|| detect_repo(&cwd))
+                .unwrap_or_default(),
+        )
+        .allow_empty(true)
+        .interact_text()
+        .map_err(prompt_error)?;
+    schema.repo = Some(repo.trim().to_string()).filter(|r| !r.is_empty());
+
+    let labels: String = Input::new()
+        .with_prompt("Labels (comma-separated)")
+        .default(schema.labels.join(", "))
+        .allow_empty(true)
+        .interact_text()
+        .map_err(prompt_error)?;
+    schema.labels = split_list(&labels);
+
+    edit_fields(&mut schema)?;
+    edit_automated(&mut schema)?;
+
+    Ok(schema)
+}
+
+fn edit_fields(schema: &mut GripeSchema) -> Result<(), GripeError> {
+    if !schema.fields.is_empty() {
+        let items: Vec<String> = schema
+            .fields
+            .iter()
+            .map(|f| {
+                format!(
+                    "{} ({}{})",
+                    f.id,
+                    FIELD_TYPES[field_type_index(&f.field_type)],
+                    if f.required { ", required" } else { "" }
+                )
+            })
+            .collect();
+        let keep = MultiSelect::new()
+            .with_prompt("Fields to keep (space toggles, enter confirms)")
+            .items_checked(&items.iter().map(|i| (i.as_str(), true)).collect::<Vec<_>>())
+            .interact()
+            .map_err(prompt_error)?;
+        schema.fields = keep.into_iter().map(|i| schema.fields[i].clone()).collect();
+    }
+
+    while Confirm::new()
+        .with_prompt("Add a field?")
+        .default(schema.fields.is_empty())
+        .interact()
+        .map_err(prompt_error)?
+    {
+        let field = prompt_field(&schema.fields)?;
+        schema.fields.push(field);
+    }
+    Ok(())
+}
+
+fn prompt_field(existing: &[FieldDefinition]) -> Result<FieldDefinition, GripeError> {
+    let ids: Vec<String> = existing.iter().map(|f| f.id.clone()).collect();
+    let id: String = Input::new()
+        .with_prompt("Field id")
+        .validate_with(move |id: &String| -> Result<(), String> {
+            if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                Err("Use letters, digits and underscores".to_string())
+            } else if ids.contains(id) {
+                Err(format!("There is already a field '{}'", id))
+            } else {
+                Ok(())
+            }
+        })
+        .interact_text()
+        .map_err(prompt_error)?;
+    let label: String = Input::new()
+        .with_prompt("Label")
+        .default(id.clone())
+        .interact_text()
+        .map_err(prompt_error)?;
+    let type_index = Select::new()
+        .with_prompt("Type")
+        .items(FIELD_TYPES)
+        .default(0)
+        .interact()
+        .map_err(prompt_error)?;
+    let field_type: FieldType = serde_yaml::from_str(FIELD_TYPES[type_index])
+        .map_err(|e| GripeError::Other(format!("Unknown field type: {}", e)))?;
+
+    let options = if field_type == FieldType::Select {
+        let options: String = Input::new()
+            .with_prompt("Options (comma-separated)")
+            .validate_with(|o: &String| -> Result<(), &str> {
+                if split_list(o).is_empty() {
+                    Err("A select field needs at least one option")
+                } else {
+                    Ok(())
+                }
+            })
+            .interact_text()
+            .map_err(prompt_error)?;
+        split_list(&options)
+    } else {
+        Vec::new()
+    };
+    let required = Confirm::new()
+        .with_prompt("Required?")
+        .default(false)
+        .interact()
+        .map_err(prompt_error)?;
+
+    Ok(FieldDefinition {
+        label: Some(label).filter(|l| l != &id),
+        id,
+        description: None,
+        field_type,
+        required,
+        options,
+        default: None,
+        pattern: None,
+        min: None,
+        max: None,
+    })
+}
+
+fn edit_automated(schema: &mut GripeSchema) -> Result<(), GripeError> {
+    let modes = [
+        "allow  - accept submissions from scripts and agents",
+        "deny   - only accept interactive submissions",
+        "review - send automated submissions somewhere for triage",
+    ];
+    let current = match schema.automated.mode {
+        AutomatedMode::Allow => 0,
+        AutomatedMode::Deny => 1,
+        AutomatedMode::Review => 2,
+    };
+    let mode = Select::new()
+        .with_prompt("Automated submissions")
+        .items(&modes)
+        .default(current)
+        .interact()
+        .map_err(prompt_error)?;
+    schema.automated.mode = match mode {
+        0 => AutomatedMode::Allow,
+        1 => AutomatedMode::Deny,
+        _ => AutomatedMode::Review,
+    };
+
+    if schema.automated.mode == AutomatedMode::Review {
+        let current = schema.automated.review.clone();
+        let destinations = [
+            "a triage repository",
+            "a discussion category in the target repo",
+        ];
+        let to_discussion = Select::new()
+            .with_prompt("Send automated gripes to")
+            .items(&destinations)
+            .default(
+                current
+                    .as_ref()
+                    .map_or(0, |t| t.discussion_category.is_some() as usize),
+            )
+            .interact()
+            .map_err(prompt_error)?
+            == 1;
+
+        let mut prompt = Input::<String>::new();
+        let target = if to_discussion {
+            if let Some(category) = current.and_then(|t| t.discussion_category) {
+                prompt = prompt.default(category);
+            }
+            let category = prompt
+                .with_prompt("Discussion category")
+                .interact_text()
+                .map_err(prompt_error)?;
+            ReviewTarget {
+                repo: None,
+                discussion_category: Some(category.trim().to_string()),
+            }
+        } else {
+            if let Some(repo) = current.and_then(|t| t.repo) {
+                prompt = prompt.default(repo);
+            }
+            let repo = prompt
+                .with_prompt("Triage repository (owner/repo)")
+                .validate_with(|repo: &String| -> Result<(), &str> {
+                    let parts = repo.trim().split('/').filter(|part| !part.is_empty());
+                    if parts.count() == 2 {
+                        Ok(())
+                    } else {
+                        Err("Enter the repository as owner/repo")
+                    }
+                })
+                .interact_text()
+                .map_err(prompt_error)?;
+            ReviewTarget {
+                repo: Some(repo.trim().to_string()),
+                discussion_category: None,
+            }
+        };
+        schema.automated.review = Some(target);
+    }
+    if schema.automated.mode != AutomatedMode::Deny {
+        schema.automated.require_reporter = Confirm::new()
+            .with_prompt("Require automated submissions to name a reporter?")
+            .default(schema.automated.require_reporter)
+            .interact()
+            .map_err(prompt_error)?;
+    }
+    Ok(())
+}
+
+fn field_type_index(field_type: &FieldType) -> usize {
+    match field_type {
+        FieldType::Input => 0,
+        FieldType::Textarea => 1,
+        FieldType::Select => 2,
+        FieldType::Number => 3,
+        FieldType::Checkbox => 4,
+        FieldType::List => 5,
+        FieldType::File => 6,
+    }
+}
+
+fn split_list(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn prompt_error(e: dialoguer::Error) -> GripeError {
+    GripeError::Other(format!("Prompt error: {}", e))
+}
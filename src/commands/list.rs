@@ -0,0 +1,109 @@
+use clap::Args;
+use colored::Colorize;
+
+use crate::body_formatter::parse_marker;
+use crate::error::GripeError;
+use crate::gh::{self, IssueQuery};
+
+/// Most issues scanned for gripe markers, however many are shown.
+const MAX_SCANNED: usize = 1000;
+
+#[derive(Args)]
+pub struct ListArgs {
+    /// Only show issues in this state
+    #[arg(long, default_value = "open", value_parser = ["open", "closed", "all"])]
+    state: String,
+    /// Only show issues in this repository (owner/repo)
+    #[arg(long)]
+    repo: Option<String>,
+    /// Only show issues filed with the schema of this name
+    #[arg(long)]
+    schema: Option<String>,
+    /// Only show issues with this label (repeatable)
+    #[arg(long = "label", value_name = "LABEL")]
+    labels: Vec<String>,
+    /// Maximum number of issues to show
+    #[arg(long, default_value_t = 30)]
+    limit: usize,
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// List issues the current user filed with gripe, found by the marker
+/// `submit` leaves in each issue body.
+pub fn run(args: ListArgs) -> Result<(), GripeError> {
+    gh::check_gh_available()?;
+
+    // Other issues by the same author are filtered out below, so look
+    // through more than will be shown
+    let query = IssueQuery {
+        repo: args.repo,
+        state: (args.state != "all").then_some(args.state),
+        labels: args.labels,
+        limit: (args.limit * 5).clamp(100, MAX_SCANNED),
+    };
+    let issues: Vec<_> = gh::search_my_issues(&query)?
+        .into_iter()
+        .filter_map(|issue| {
+            let schema = parse_marker(&issue.body)?;
+            match &args.schema {
+                Some(wanted) if schema.as_deref() != Some(wanted.as_str()) => None,
+                _ => Some((issue, schema)),
+            }
+        })
+        .take(args.limit)
+        .collect();
+
+    if args.json {
+        let output: Vec<_> = issues
+            .iter()
+            .map(|(issue, schema)| {
+                serde_json::json!({
+                    "repo": issue.repo,
+                    "number": issue.number,
+                    "title": issue.title,
+                    "url": issue.url,
+                    "state": issue.state,
+                    "labels": issue.labels,
+                    "schema": schema,
+                    "created_at": issue.created_at,
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output)
+                .map_err(|e| GripeError::Other(format!("JSON error: {}", e)))?
+        );
+        return Ok(());
+    }
+
+    if issues.is_empty() {
+        println!("No gripes found.");
+        return Ok(());
+    }
+
+    let width = issues
+        .iter()
+        .map(|(i, _)| i.repo.len() + i.number.to_string().len() + 1)
+        .max()
+        .unwrap_or(0);
+    for (issue, _) in &issues {
+        let id = format!("{:<width$}", format!("{}#{}", issue.repo, issue.number));
+        let state = match issue.state.as_str() {
+            "open" => format!("{:<6}", "open").green(),
+            other => format!("{:<6}", other).purple(),
+        };
+        let date = issue.created_at.get(..10).unwrap_or(&issue.created_at);
+        println!(
+            "{}  {}  {}  {}",
+            id.cyan(),
+            state,
+            date.dimmed(),
+            issue.title
+        );
+    }
+
+    Ok(())
+}
@@ -1,3 +1,10 @@
+pub mod comment;
+pub mod convert;
+pub mod history;
 pub mod init;
+pub mod list;
+pub mod policy;
 pub mod schema;
+pub mod status;
 pub mod submit;
+pub mod validate;
@@ -0,0 +1,80 @@
+use colored::Colorize;
+
+use crate::config::resolve_schema_with_source;
+use crate::error::GripeError;
+use crate::gh;
+use crate::policy::{self, PolicySource};
+use crate::types::AutomatedPolicy;
+
+/// Print the local, remote and effective automated policies for a repo.
+pub fn show(repo: Option<String>, json: bool, refresh: bool) -> Result<(), GripeError> {
+    let (schema, source) = resolve_schema_with_source()?;
+    let repo = repo.or_else(|| schema.repo.clone()).ok_or_else(|| {
+        GripeError::Validation(
+            "No repo specified. Pass a repo, set repo in gripe.yaml, or run from a git repo."
+                .to_string(),
+        )
+    })?;
+
+    gh::check_gh_available()?;
+    let policies = policy::load_policies(&schema, &source, &repo, refresh)?;
+    let effective = policy::merge(&policies.iter().map(|p| &p.policy).collect::<Vec<_>>());
+    let remote = policies
+        .iter()
+        .find(|p| matches!(p.source, PolicySource::Remote(_)));
+
+    if json {
+        let output = serde_json::json!({
+            "repo": repo,
+            "policies": policies
+                .iter()
+                .map(|p| serde_json::json!({
+                    "source": p.source.to_string(),
+                    "policy": p.policy,
+                }))
+                .collect::<Vec<_>>(),
+            "effective": effective,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output)
+                .map_err(|e| GripeError::Other(format!("JSON error: {}", e)))?
+        );
+        return Ok(());
+    }
+
+    println!("{}: {}", "Repo".bold(), repo);
+    for sourced in &policies {
+        println!();
+        print_policy(&capitalize(&sourced.source.to_string()), &sourced.policy)?;
+    }
+    if remote.is_none() {
+        println!();
+        println!(
+            "{}",
+            format!("{} has no gripe.yaml; no remote policy applies.", repo).dimmed()
+        );
+    }
+    println!();
+    print_policy("Effective policy", &effective)?;
+
+    Ok(())
+}
+
+fn print_policy(heading: &str, policy: &AutomatedPolicy) -> Result<(), GripeError> {
+    println!("{}", format!("{}:", heading).bold());
+    let yaml = serde_yaml::to_string(policy)
+        .map_err(|e| GripeError::Other(format!("Failed to serialize policy: {}", e)))?;
+    for line in yaml.lines() {
+        println!("  {}", line);
+    }
+    Ok(())
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
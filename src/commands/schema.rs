@@ -1,15 +1,27 @@
 use colored::Colorize;
 
-use crate::config::resolve_schema;
+use crate::config::{resolve_remote_schema, resolve_schema_with_source};
+use crate::error::GripeError;
+use crate::json_schema::to_json_schema;
 
-pub fn run(json: bool) -> Result<(), String> {
-    let schema = resolve_schema()?;
+pub fn run(format: &str, repo: Option<String>) -> Result<(), GripeError> {
+    let (schema, source) = match repo {
+        Some(repo) => resolve_remote_schema(&repo, false)?.ok_or_else(|| {
+            GripeError::NotFound(format!("{} has no gripe.yaml or issue forms", repo))
+        })?,
+        None => resolve_schema_with_source()?,
+    };
 
-    if json {
+    if format == "json-schema" {
+        let output = serde_json::to_string_pretty(&to_json_schema(&schema))
+            .map_err(|e| GripeError::Other(format!("Failed to serialize: {}", e)))?;
+        println!("{}", output);
+    } else if format == "json" {
         let output = serde_json::to_string_pretty(&schema)
-            .map_err(|e| format!("Failed to serialize: {}", e))?;
+            .map_err(|e| GripeError::Other(format!("Failed to serialize: {}", e)))?;
         println!("{}", output);
     } else {
+        println!("{}: {}", "Source".bold(), source);
         if let Some(repo) = &schema.repo {
             println!("{}: {}", "Repo".bold(), repo);
         }
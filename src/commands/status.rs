@@ -0,0 +1,147 @@
+use std::thread;
+use std::time::Duration;
+
+use clap::Args;
+use colored::Colorize;
+
+use crate::config::resolve_schema;
+use crate::error::GripeError;
+use crate::gh::{self, IssueStatus};
+use crate::history;
+
+/// Lines of each comment shown before it's cut off.
+const COMMENT_LINES: usize = 4;
+
+#[derive(Args)]
+pub struct StatusArgs {
+    /// Issue number, owner/repo#number or issue URL (default: your last submission)
+    issue: Option<String>,
+    /// Repository of the issue, when giving just its number
+    #[arg(long)]
+    repo: Option<String>,
+    /// Number of latest comments to show
+    #[arg(long, default_value_t = 3)]
+    comments: usize,
+    /// Keep checking, printing changes, until the issue is closed
+    #[arg(long)]
+    watch: bool,
+    /// Seconds between checks with --watch
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u64).range(10..))]
+    interval: u64,
+    /// Output as JSON (one line per change with --watch)
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Show where an issue stands, optionally waiting for it to be closed.
+pub fn run(args: StatusArgs) -> Result<(), GripeError> {
+    let (repo, number) = target(&args)?;
+    gh::check_gh_available()?;
+
+    let mut last: Option<IssueStatus> = None;
+    loop {
+        let status = match gh::issue_status(&repo, number, args.comments) {
+            Ok(status) => status,
+            // A dropped connection shouldn't end a long watch
+            Err(GripeError::Network(e)) if args.watch && last.is_some() => {
+                eprintln!("{} {}; retrying", "warning:".yellow(), e);
+                thread::sleep(Duration::from_secs(args.interval));
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        if last.as_ref() != Some(&status) {
+            print_status(&status, args.json, args.watch)?;
+        }
+        if !args.watch || status.state == "closed" {
+            return Ok(());
+        }
+        last = Some(status);
+        thread::sleep(Duration::from_secs(args.interval));
+    }
+}
+
+/// The issue to show: the one given, or the last issue in the history log.
+fn target(args: &StatusArgs) -> Result<(String, u64), GripeError> {
+    if let Some(issue) = &args.issue {
+        let default_repo = match &args.repo {
+            Some(repo) => Some(repo.clone()),
+            None => resolve_schema()?.repo,
+        };
+        return gh::parse_issue_ref(issue, default_repo.as_deref());
+    }
+
+    history::load()?
+        .into_iter()
+        .rev()
+        .filter(|e| e.kind == "issue")
+        .find(|e| args.repo.as_ref().is_none_or(|r| &e.repo == r))
+        .map(|e| (e.repo, e.number))
+        .ok_or_else(|| {
+            GripeError::NotFound(
+                "No submitted issues recorded; pass an issue number or URL.".to_string(),
+            )
+        })
+}
+
+fn print_status(status: &IssueStatus, json: bool, watch: bool) -> Result<(), GripeError> {
+    if json {
+        let output = if watch {
+            serde_json::to_string(status)
+        } else {
+            serde_json::to_string_pretty(status)
+        };
+        println!(
+            "{}",
+            output.map_err(|e| GripeError::Other(format!("JSON error: {}", e)))?
+        );
+        return Ok(());
+    }
+
+    let state = match (status.state.as_str(), &status.state_reason) {
+        ("open", _) => "open".green(),
+        (state, Some(reason)) => format!("{} ({})", state, reason.replace('_', " ")).purple(),
+        (state, None) => state.purple(),
+    };
+    println!(
+        "{}  {}",
+        format!("{}#{}", status.repo, status.number).cyan(),
+        status.title.bold()
+    );
+    println!("{}: {}", "State".bold(), state);
+    if !status.labels.is_empty() {
+        println!("{}: {}", "Labels".bold(), status.labels.join(", "));
+    }
+    if !status.assignees.is_empty() {
+        println!("{}: {}", "Assignees".bold(), status.assignees.join(", "));
+    }
+    if !status.linked_prs.is_empty() {
+        println!("{}:", "Linked PRs".bold());
+        for pr in &status.linked_prs {
+            let state = match pr.state.as_str() {
+                "merged" => pr.state.purple(),
+                "open" => pr.state.green(),
+                _ => pr.state.red(),
+            };
+            println!("  #{} {}  {}", pr.number, state, pr.title);
+        }
+    }
+    if !status.comments.is_empty() {
+        println!("{}:", "Latest comments".bold());
+        for comment in &status.comments {
+            let date = comment.created_at.get(..10).unwrap_or(&comment.created_at);
+            println!("  {} {}", comment.author.cyan(), date.dimmed());
+            let lines: Vec<&str> = comment.body.trim().lines().collect();
+            for line in lines.iter().take(COMMENT_LINES) {
+                println!("    {}", line);
+            }
+            if lines.len() > COMMENT_LINES {
+                println!("    {}", "…".dimmed());
+            }
+        }
+    }
+    println!("{}", status.url.underline());
+
+    Ok(())
+}
@@ -1,23 +1,111 @@
 use std::collections::HashMap;
+use std::env;
 use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 
+use clap::Args;
 use colored::Colorize;
-use dialoguer::{Input, Select};
+use dialoguer::{Confirm, Input, Select};
+use serde_json::Value;
 
-use crate::body_formatter::{format_body, format_title};
-use crate::config::resolve_schema;
-use crate::gh;
-use crate::types::{AutomatedPolicy, FieldType, GripeSchema};
+use crate::attachments::Attachment;
+use crate::body_formatter::{
+    format_body, format_title, marker, overflow_comments, FormattedBody, MAX_BODY_CHARS,
+};
+use crate::codeowners::{self, CodeOwners};
+use crate::config::{resolve_remote_schema, resolve_schema_with_source};
+use crate::dates::{now_utc, today_utc};
+use crate::error::GripeError;
+use crate::gh::{self, IssueMeta};
+use crate::history;
+use crate::lint::closest;
+use crate::policy::{self, Decision};
+use crate::redact::Redactor;
+use crate::routing::{self, extend_unique};
+use crate::types::{ExtraFields, FieldType, GripeSchema, ReviewTarget};
+use crate::values;
 
-pub fn run(
-    json_str: Option<String>,
+#[derive(Args)]
+pub struct SubmitArgs {
+    /// JSON string with field values
+    #[arg(long)]
+    json: Option<String>,
+    /// Read JSON from stdin
+    #[arg(long)]
     stdin: bool,
+    /// Preview without creating an issue
+    #[arg(long)]
     dry_run: bool,
-    output_json: bool,
-    repo_override: Option<String>,
-    field_args: Vec<String>,
-) -> Result<(), String> {
-    let schema = resolve_schema()?;
+    /// Output result as JSON
+    #[arg(long)]
+    pub output_json: bool,
+    /// Target repository (owner/repo)
+    #[arg(long)]
+    repo: Option<String>,
+    /// Identify the submitting agent for automated policies [env: GRIPE_AGENT]
+    #[arg(long)]
+    agent: Option<String>,
+    /// Re-fetch the target repo's policy and schema instead of using the cache
+    #[arg(long)]
+    refresh_policy: bool,
+    /// Use the local schema even when --repo names another repository
+    #[arg(long)]
+    local_schema: bool,
+    /// Attach a text file (embedded, or uploaded as a gist if large)
+    #[arg(long, value_name = "PATH")]
+    attach: Vec<PathBuf>,
+    /// Assign the issue to a user, instead of the schema's assignees (repeatable)
+    #[arg(long = "assignee", value_name = "LOGIN")]
+    assignees: Vec<String>,
+    /// Milestone title, instead of the schema's
+    #[arg(long)]
+    milestone: Option<String>,
+    /// Add the issue to a project, instead of the schema's projects (repeatable)
+    #[arg(long = "project", value_name = "OWNER/NUMBER")]
+    projects: Vec<String>,
+    /// Issue type, such as Bug, instead of the schema's
+    #[arg(long = "type", value_name = "TYPE")]
+    issue_type: Option<String>,
+    /// Field values as key=value pairs
+    #[arg(trailing_var_arg = true)]
+    fields: Vec<String>,
+}
+
+pub fn run(args: SubmitArgs) -> Result<(), GripeError> {
+    let SubmitArgs {
+        json: json_str,
+        stdin,
+        dry_run,
+        output_json,
+        repo: repo_override,
+        agent,
+        refresh_policy,
+        local_schema: local_schema_only,
+        attach,
+        assignees,
+        milestone,
+        projects,
+        issue_type,
+        fields: field_args,
+    } = args;
+    let (local_schema, local_source) = resolve_schema_with_source()?;
+
+    // Filing against another repo uses the schema that repo publishes. The
+    // local one is only a fallback when the repo publishes none; failing to
+    // fetch it is an error, since the local schema is the wrong one to file with
+    let remote = match &repo_override {
+        Some(target) if !local_schema_only && local_schema.repo.as_ref() != Some(target) => {
+            resolve_remote_schema(target, refresh_policy).map_err(|e| {
+                e.with_message(format!(
+                    "Could not load the schema for {} (pass --local-schema to use yours): {}",
+                    target, e
+                ))
+            })?
+        }
+        _ => None,
+    };
+    let using_remote = remote.is_some();
+    let (schema, source) = remote.unwrap_or_else(|| (local_schema.clone(), local_source.clone()));
     let is_interactive;
 
     // Determine input mode and collect values
@@ -29,7 +117,7 @@ pub fn run(
         let mut buf = String::new();
         io::stdin()
             .read_to_string(&mut buf)
-            .map_err(|e| format!("Failed to read stdin: {}", e))?;
+            .map_err(|e| GripeError::Other(format!("Failed to read stdin: {}", e)))?;
         parse_json_input(buf.trim())?
     } else if !field_args.is_empty() {
         is_interactive = false;
@@ -38,43 +126,117 @@ pub fn run(
         is_interactive = true;
         interactive_prompt(&schema)?
     };
+    let values = values::normalize(&schema, values).map_err(GripeError::Validation)?;
 
     // Validate required fields
     validate_fields(&schema, &values)?;
+    check_extra_fields(&schema, &values)?;
 
     // Resolve repo
     let repo = repo_override
         .or_else(|| schema.repo.clone())
         .ok_or_else(|| {
-            "No repo specified. Use --repo, set repo in gripe.yaml, or run from a git repo."
-                .to_string()
+            GripeError::Validation(
+                "No repo specified. Use --repo, set repo in gripe.yaml, or run from a git repo."
+                    .to_string(),
+            )
         })?;
 
-    let title = format_title(schema.title_template.as_deref(), &values);
-    let body = format_body(&schema, &values);
+    let mut attachments = load_attachments(&schema, &values, &attach)?;
+
+    // Redact secrets before anything is rendered or uploaded
+    // The submitter's own redaction rules always apply, plus any the target adds
+    let mut redact = local_schema.redact.clone();
+    if using_remote {
+        redact
+            .patterns
+            .extend(schema.redact.patterns.iter().cloned());
+    }
+    let redactor = Redactor::new(&redact).map_err(GripeError::ConfigParse)?;
+    let values = redact_inputs(&redactor, values, &mut attachments);
+
+    let mut meta = routing::resolve(&schema, &values);
+    if let Some(config) = &schema.codeowners {
+        let remote_repo = if using_remote {
+            Some(repo.as_str())
+        } else {
+            None
+        };
+        match codeowners::load(remote_repo, refresh_policy) {
+            Ok(Some(contents)) => extend_unique(
+                &mut meta.assignees,
+                codeowners::assignees(config, &values, &CodeOwners::parse(&contents)),
+            ),
+            Ok(None) => eprintln!(
+                "{} codeowners is configured but no CODEOWNERS file was found",
+                "warning:".yellow()
+            ),
+            Err(e) => eprintln!("{} Could not read CODEOWNERS: {}", "warning:".yellow(), e),
+        }
+    }
+    if !assignees.is_empty() {
+        meta.assignees = assignees.iter().map(|a| routing::login(a)).collect();
+    }
+    if !projects.is_empty() {
+        meta.projects = projects;
+    }
+    meta.milestone = milestone.or(meta.milestone);
+    meta.issue_type = issue_type.or(meta.issue_type);
+
+    let title =
+        format_title(schema.title_template.as_deref(), &values).map_err(GripeError::ConfigParse)?;
 
     if dry_run {
+        let body = issue_body(&schema, &values, &attachments)?;
+        let truncated: Vec<&str> = body.overflow.iter().map(|o| o.label.as_str()).collect();
         if output_json {
             let output = serde_json::json!({
                 "repo": repo,
                 "title": title,
-                "body": body,
-                "labels": schema.labels,
+                "body": body.text,
+                "labels": meta.labels,
+                "assignees": meta.assignees,
+                "milestone": meta.milestone,
+                "projects": meta.projects,
+                "type": meta.issue_type,
                 "fields": values,
+                "truncated": truncated,
+                "schema_source": source.to_string(),
             });
             println!(
                 "{}",
-                serde_json::to_string_pretty(&output).map_err(|e| format!("JSON error: {}", e))?
+                serde_json::to_string_pretty(&output)
+                    .map_err(|e| GripeError::Other(format!("JSON error: {}", e)))?
             );
         } else {
             println!("{}", "--- Dry Run ---".yellow().bold());
             println!("{}: {}", "Repo".bold(), repo);
+            println!("{}: {}", "Schema".bold(), source);
             println!("{}: {}", "Title".bold(), title);
-            if !schema.labels.is_empty() {
-                println!("{}: {}", "Labels".bold(), schema.labels.join(", "));
+            if !meta.labels.is_empty() {
+                println!("{}: {}", "Labels".bold(), meta.labels.join(", "));
+            }
+            if !meta.assignees.is_empty() {
+                println!("{}: {}", "Assignees".bold(), meta.assignees.join(", "));
+            }
+            if let Some(milestone) = &meta.milestone {
+                println!("{}: {}", "Milestone".bold(), milestone);
+            }
+            if !meta.projects.is_empty() {
+                println!("{}: {}", "Projects".bold(), meta.projects.join(", "));
+            }
+            if let Some(issue_type) = &meta.issue_type {
+                println!("{}: {}", "Type".bold(), issue_type);
+            }
+            if !truncated.is_empty() {
+                println!(
+                    "{}: {} (full text will be posted as comments)",
+                    "Truncated".bold(),
+                    truncated.join(", ")
+                );
             }
             println!();
-            println!("{}", body);
+            println!("{}", body.text);
         }
         return Ok(());
     }
@@ -83,30 +245,150 @@ pub fn run(
     gh::check_gh_available()?;
 
     // Check robots policy for non-interactive submissions
+    let mut review = None;
     if !is_interactive {
-        match gh::check_robots(&repo)? {
-            AutomatedPolicy::Deny => {
-                return Err(format!(
-                    "This repository does not accept automated feedback. See: https://github.com/{}/blob/main/gripe.yaml",
-                    repo
-                ));
+        let policies = policy::load_policies(&local_schema, &local_source, &repo, refresh_policy)?;
+        let effective = policy::merge(&policies.iter().map(|p| &p.policy).collect::<Vec<_>>());
+        let agent = agent
+            .or_else(|| env::var("GRIPE_AGENT").ok())
+            .filter(|a| !a.is_empty());
+        let (decision, decided_by) =
+            policy::evaluate_all(&policies, &repo, agent.as_deref(), &values, |target| {
+                gh::count_my_submissions_since(target, &today_utc())
+            })?;
+        match decision {
+            Decision::Deny(reason) => {
+                let source = decided_by
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "policy".to_string());
+                return Err(GripeError::PolicyDenied(format!(
+                    "Rejected by the {}: {}. Run `gripe policy show {}` for details.",
+                    source, reason, repo
+                )));
             }
-            AutomatedPolicy::Allow => {}
+            Decision::Review(target) => review = Some(target),
+            Decision::Allow => {}
         }
+        extend_unique(&mut meta.labels, effective.labels);
     }
 
-    let result = gh::create_issue(&repo, &title, &body, &schema.labels)?;
+    for attachment in attachments.iter_mut().filter(|a| a.needs_gist()) {
+        let desc = format!("{} (attachment for: {})", attachment.file_name(), title);
+        attachment.gist_url = Some(gh::create_gist(
+            &attachment.file_name(),
+            &attachment.content,
+            &desc,
+        )?);
+    }
+    let body = issue_body(&schema, &values, &attachments)?;
+    if body.text.chars().count() > MAX_BODY_CHARS {
+        return Err(GripeError::Validation(format!(
+            "Issue body is {} characters even after truncation; GitHub allows at most {}.",
+            body.text.chars().count(),
+            MAX_BODY_CHARS
+        )));
+    }
+
+    let (result, filed_repo, kind) = match review {
+        Some(ReviewTarget {
+            repo: Some(triage), ..
+        }) => (
+            // Assignees, milestones and projects belong to the target repo
+            gh::create_issue(
+                &triage,
+                &title,
+                &body.text,
+                &IssueMeta {
+                    labels: meta.labels.clone(),
+                    ..Default::default()
+                },
+                schema.missing_labels,
+                &schema.label_definitions,
+            )?,
+            triage,
+            "issue",
+        ),
+        Some(ReviewTarget {
+            discussion_category: Some(category),
+            ..
+        }) => (
+            gh::create_discussion(&repo, &category, &title, &body.text)?,
+            repo.clone(),
+            "discussion",
+        ),
+        _ => (
+            gh::create_issue(
+                &repo,
+                &title,
+                &body.text,
+                &meta,
+                schema.missing_labels,
+                &schema.label_definitions,
+            )?,
+            repo.clone(),
+            "issue",
+        ),
+    };
+
+    let entry = history::Entry {
+        timestamp: now_utc(),
+        repo: filed_repo.clone(),
+        number: result.number,
+        url: result.url.clone(),
+        title: title.clone(),
+        kind: kind.to_string(),
+        fields: values.clone(),
+        schema_source: source.to_string(),
+    };
+    if let Err(e) = history::append(&entry) {
+        eprintln!(
+            "{} Could not record submission history: {}",
+            "warning:".yellow(),
+            e
+        );
+    }
+
+    // The issue exists from here on, so failures are reported alongside it
+    // rather than as an error a caller might retry
+    let mut warnings = Vec::new();
+    if kind == "issue" {
+        for comment in overflow_comments(&body.overflow) {
+            if let Err(e) = gh::add_comment(&filed_repo, result.number, &comment) {
+                warnings.push(format!("Truncated fields were not posted in full: {}", e));
+                break;
+            }
+        }
+    } else if !body.overflow.is_empty() {
+        warnings.push(
+            "Truncated fields were not posted in full; discussions don't take follow-up comments from gripe"
+                .to_string(),
+        );
+    }
+    for warning in &warnings {
+        eprintln!("{} {}", "warning:".yellow(), warning);
+    }
 
     if output_json {
         let output = serde_json::json!({
             "url": result.url,
             "number": result.number,
-            "repo": repo,
+            "repo": filed_repo,
             "title": title,
+            "kind": kind,
+            "review": filed_repo != repo || kind == "discussion",
+            "warnings": warnings,
         });
         println!(
             "{}",
-            serde_json::to_string_pretty(&output).map_err(|e| format!("JSON error: {}", e))?
+            serde_json::to_string_pretty(&output)
+                .map_err(|e| GripeError::Other(format!("JSON error: {}", e)))?
+        );
+    } else if kind == "discussion" || filed_repo != repo {
+        println!(
+            "{} {} does not take automated issues directly; sent for review: {}",
+            "✓".green(),
+            repo,
+            result.url.underline()
         );
     } else {
         println!("{} Issue created: {}", "✓".green(), result.url.underline());
@@ -115,50 +397,164 @@ pub fn run(
     Ok(())
 }
 
-fn parse_json_input(input: &str) -> Result<HashMap<String, String>, String> {
-    let map: HashMap<String, serde_json::Value> =
-        serde_json::from_str(input).map_err(|e| format!("Invalid JSON: {}", e))?;
+/// Format the issue body and tag it with the gripe marker.
+fn issue_body(
+    schema: &GripeSchema,
+    values: &HashMap<String, String>,
+    attachments: &[Attachment],
+) -> Result<FormattedBody, GripeError> {
+    let mut body = format_body(schema, values, attachments).map_err(GripeError::ConfigParse)?;
+    body.text.push_str("\n\n");
+    body.text.push_str(&marker(schema.name.as_deref()));
+    Ok(body)
+}
 
-    Ok(map
-        .into_iter()
-        .map(|(k, v)| {
-            let s = match v {
-                serde_json::Value::String(s) => s,
-                other => other.to_string(),
-            };
-            (k, s)
-        })
-        .collect())
+/// Parse a JSON object of field values, keeping their types for
+/// [`values::normalize`].
+pub fn parse_json_input(input: &str) -> Result<HashMap<String, Value>, GripeError> {
+    serde_json::from_str(input).map_err(|e| GripeError::Validation(format!("Invalid JSON: {}", e)))
 }
 
-fn parse_kv_args(args: &[String]) -> Result<HashMap<String, String>, String> {
+pub fn parse_kv_args(args: &[String]) -> Result<HashMap<String, Value>, GripeError> {
     let mut values = HashMap::new();
     for arg in args {
-        let (key, val) = arg
-            .split_once('=')
-            .ok_or_else(|| format!("Invalid field argument '{}'. Expected key=value.", arg))?;
-        values.insert(key.to_string(), val.to_string());
+        let (key, val) = arg.split_once('=').ok_or_else(|| {
+            GripeError::Validation(format!(
+                "Invalid field argument '{}'. Expected key=value.",
+                arg
+            ))
+        })?;
+        values.insert(key.to_string(), Value::String(val.to_string()));
     }
     Ok(values)
 }
 
-fn validate_fields(schema: &GripeSchema, values: &HashMap<String, String>) -> Result<(), String> {
+/// Load the files referenced by `file` fields and `--attach` arguments.
+pub fn load_attachments(
+    schema: &GripeSchema,
+    values: &HashMap<String, String>,
+    attach: &[PathBuf],
+) -> Result<Vec<Attachment>, GripeError> {
+    let mut attachments = Vec::new();
+
+    for field in schema
+        .fields
+        .iter()
+        .filter(|f| f.field_type == FieldType::File)
+    {
+        if let Some(path) = values.get(&field.id).filter(|v| !v.is_empty()) {
+            attachments.push(
+                Attachment::load(Some(&field.id), Path::new(path))
+                    .map_err(GripeError::Validation)?,
+            );
+        }
+    }
+
+    for path in attach {
+        attachments.push(Attachment::load(None, path).map_err(GripeError::Validation)?);
+    }
+
+    Ok(attachments)
+}
+
+/// Run field values and attachment contents through the redactor, warning
+/// about anything that was replaced.
+pub fn redact_inputs(
+    redactor: &Redactor,
+    values: HashMap<String, String>,
+    attachments: &mut [Attachment],
+) -> HashMap<String, String> {
+    let mut report = Vec::new();
+
+    let values = values
+        .into_iter()
+        .map(|(key, val)| {
+            let (clean, findings) = redactor.redact(&val);
+            report.extend(findings.into_iter().map(|f| (f, key.clone())));
+            (key, clean)
+        })
+        .collect();
+
+    for attachment in attachments.iter_mut() {
+        let (clean, findings) = redactor.redact(&attachment.content);
+        let name = attachment.file_name();
+        report.extend(findings.into_iter().map(|f| (f, name.clone())));
+        attachment.content = clean;
+    }
+
+    if !report.is_empty() {
+        eprintln!(
+            "{} Redacted possible secrets before filing:",
+            "warning:".yellow()
+        );
+        for (finding, location) in report {
+            let count = if finding.count > 1 {
+                format!("{}x ", finding.count)
+            } else {
+                String::new()
+            };
+            eprintln!("  - {}{} in {}", count, finding.kind, location);
+        }
+    }
+
+    values
+}
+
+pub fn validate_fields(
+    schema: &GripeSchema,
+    values: &HashMap<String, String>,
+) -> Result<(), GripeError> {
     let missing: Vec<&str> = schema
         .fields
         .iter()
         .filter(|f| f.required)
-        .filter(|f| values.get(&f.id).map(|v| v.is_empty()).unwrap_or(true))
+        .filter(|f| match values.get(&f.id) {
+            // A required checkbox has to be ticked, as in GitHub's issue forms
+            Some(v) if f.field_type == FieldType::Checkbox => v != "true",
+            Some(v) => v.is_empty(),
+            None => true,
+        })
         .map(|f| f.display_label())
         .collect();
 
     if !missing.is_empty() {
-        return Err(format!("Missing required fields: {}", missing.join(", ")));
+        return Err(GripeError::Validation(format!(
+            "Missing required fields: {}",
+            missing.join(", ")
+        )));
     }
 
     Ok(())
 }
 
-fn interactive_prompt(schema: &GripeSchema) -> Result<HashMap<String, String>, String> {
+/// Refuse values whose key isn't a field id, unless the schema's
+/// `extra_fields` lets them through.
+pub fn check_extra_fields(
+    schema: &GripeSchema,
+    values: &HashMap<String, String>,
+) -> Result<(), GripeError> {
+    let extra = schema.extra_keys(values);
+    if schema.extra_fields != ExtraFields::Reject || extra.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<&str> = schema.fields.iter().map(|f| f.id.as_str()).collect();
+    let unknown: Vec<String> = extra
+        .iter()
+        .map(|key| match closest(key, ids.iter().copied()) {
+            Some(id) => format!("'{}' (did you mean '{}'?)", key, id),
+            None => format!("'{}'", key),
+        })
+        .collect();
+    Err(GripeError::Validation(format!(
+        "Unknown field{} {}. Known fields: {}. Set `extra_fields: append` in gripe.yaml to include unknown fields under \"Other\".",
+        if unknown.len() == 1 { "" } else { "s" },
+        unknown.join(", "),
+        ids.join(", ")
+    )))
+}
+
+pub fn interactive_prompt(schema: &GripeSchema) -> Result<HashMap<String, Value>, GripeError> {
     let mut values = HashMap::new();
 
     for field in &schema.fields {
@@ -181,11 +577,31 @@ fn interactive_prompt(schema: &GripeSchema) -> Result<HashMap<String, String>, S
                     .items(&field.options)
                     .default(default_idx)
                     .interact()
-                    .map_err(|e| format!("Prompt error: {}", e))?;
+                    .map_err(|e| GripeError::Other(format!("Prompt error: {}", e)))?;
 
                 field.options[selection].clone()
             }
+            FieldType::Checkbox => {
+                let checked = Confirm::new()
+                    .with_prompt(&label)
+                    .default(
+                        field
+                            .default
+                            .as_deref()
+                            .and_then(values::parse_checkbox)
+                            .unwrap_or(false),
+                    )
+                    .interact()
+                    .map_err(|e| GripeError::Other(format!("Prompt error: {}", e)))?;
+                values.insert(field.id.clone(), Value::Bool(checked));
+                continue;
+            }
             _ => {
+                let label = if field.field_type == FieldType::List {
+                    format!("{} (comma-separated)", label)
+                } else {
+                    label
+                };
                 let mut prompt = Input::<String>::new().with_prompt(&label);
 
                 if let Some(ref def) = field.default {
@@ -196,14 +612,20 @@ fn interactive_prompt(schema: &GripeSchema) -> Result<HashMap<String, String>, S
                     prompt = prompt.allow_empty(true);
                 }
 
+                // List items are split out and checked after the prompt
+                if field.field_type != FieldType::List {
+                    prompt =
+                        prompt.validate_with(|input: &String| values::check_value(field, input));
+                }
+
                 prompt
                     .interact_text()
-                    .map_err(|e| format!("Prompt error: {}", e))?
+                    .map_err(|e| GripeError::Other(format!("Prompt error: {}", e)))?
             }
         };
 
         if !value.is_empty() {
-            values.insert(field.id.clone(), value);
+            values.insert(field.id.clone(), Value::String(value));
         }
     }
 
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+use colored::Colorize;
+
+use crate::config::find_gripe_yaml;
+use crate::error::GripeError;
+use crate::lint::{lint, Severity};
+
+/// Lint a gripe.yaml, by default the one `submit` would use.
+pub fn run(path: Option<PathBuf>, json: bool) -> Result<(), GripeError> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let cwd = std::env::current_dir()
+                .map_err(|e| GripeError::Other(format!("Cannot get cwd: {}", e)))?;
+            find_gripe_yaml(&cwd)
+                .ok_or_else(|| GripeError::NotFound("No gripe.yaml found".to_string()))?
+        }
+    };
+    let source = std::fs::read_to_string(&path).map_err(|e| {
+        GripeError::ConfigParse(format!("Failed to read {}: {}", path.display(), e))
+    })?;
+
+    let diagnostics = lint(&source);
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    let failure = (errors > 0).then(|| {
+        GripeError::Validation(format!(
+            "{} has {} {}",
+            path.display(),
+            errors,
+            if errors == 1 { "error" } else { "errors" }
+        ))
+    });
+
+    if json {
+        let mut output = serde_json::json!({
+            "path": path.display().to_string(),
+            "valid": errors == 0,
+            "diagnostics": diagnostics,
+        });
+        // The report doubles as the error document, so main doesn't print another
+        if let Some(e) = &failure {
+            output["error"] = e.to_json()["error"].clone();
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output)
+                .map_err(|e| GripeError::Other(format!("JSON error: {}", e)))?
+        );
+    } else {
+        for d in &diagnostics {
+            let severity = match d.severity {
+                Severity::Error => "error:".red().bold(),
+                Severity::Warning => "warning:".yellow(),
+            };
+            println!(
+                "{}:{}:{}: {} {}",
+                path.display(),
+                d.line,
+                d.column,
+                severity,
+                d.message
+            );
+        }
+        if errors == 0 {
+            let note = match warnings {
+                0 => String::new(),
+                1 => " (1 warning)".to_string(),
+                n => format!(" ({} warnings)", n),
+            };
+            println!("{} {} is valid{}", "✓".green(), path.display(), note);
+        }
+    }
+
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
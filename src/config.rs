@@ -1,13 +1,18 @@
 use std::env;
+use std::fmt;
 use std::path::{Path, PathBuf};
 
+use serde_yaml::Value;
+
 use crate::defaults::default_schema;
+use crate::error::GripeError;
+use crate::gh;
 use crate::git::{detect_repo, find_git_root};
-use crate::github_templates::load_github_templates;
-use crate::types::GripeSchema;
+use crate::github_templates::{is_template_file, load_github_templates, parse_github_template};
+use crate::types::{BodyTemplate, GripeSchema};
 
 /// Walk up from `start` looking for gripe.yaml
-fn find_gripe_yaml(start: &Path) -> Option<PathBuf> {
+pub fn find_gripe_yaml(start: &Path) -> Option<PathBuf> {
     let mut dir = start.to_path_buf();
     loop {
         let candidate = dir.join("gripe.yaml");
@@ -20,19 +25,67 @@ fn find_gripe_yaml(start: &Path) -> Option<PathBuf> {
     }
 }
 
-fn load_gripe_yaml(path: &Path) -> Result<GripeSchema, String> {
-    let contents = std::fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
-    serde_yaml::from_str(&contents)
-        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+fn load_gripe_yaml(path: &Path) -> Result<GripeSchema, GripeError> {
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        GripeError::ConfigParse(format!("Failed to read {}: {}", path.display(), e))
+    })?;
+    let mut schema: GripeSchema = serde_yaml::from_str(&contents).map_err(|e| {
+        GripeError::ConfigParse(format!("Failed to parse {}: {}", path.display(), e))
+    })?;
+
+    // Template files are relative to the gripe.yaml that names them
+    if let Some(BodyTemplate::File { file }) = &mut schema.body_template {
+        if let Some(dir) = path.parent() {
+            *file = dir.join(&*file).to_string_lossy().to_string();
+        }
+    }
+
+    Ok(schema)
+}
+
+/// Where the resolved schema came from.
+#[derive(Debug, Clone)]
+pub enum SchemaSource {
+    GripeYaml(PathBuf),
+    IssueTemplates(PathBuf),
+    /// A file fetched from the target repo (`owner/repo`, path).
+    Remote(String, String),
+    Default,
+}
+
+impl fmt::Display for SchemaSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaSource::GripeYaml(path) => write!(f, "{}", path.display()),
+            SchemaSource::IssueTemplates(dir) => write!(f, "{}", dir.display()),
+            SchemaSource::Remote(repo, path) => write!(f, "{}:{}", repo, path),
+            SchemaSource::Default => write!(f, "built-in default"),
+        }
+    }
 }
 
-/// Resolve schema using fallback chain:
+/// Resolve schema using fallback chain, reporting which source was used:
 /// 1. gripe.yaml (walk up from cwd)
 /// 2. .github/ISSUE_TEMPLATE/*.yml in git root
 /// 3. Built-in default
-pub fn resolve_schema() -> Result<GripeSchema, String> {
-    let cwd = env::current_dir().map_err(|e| format!("Cannot get cwd: {}", e))?;
+pub fn resolve_schema_with_source() -> Result<(GripeSchema, SchemaSource), GripeError> {
+    let cwd =
+        env::current_dir().map_err(|e| GripeError::Other(format!("Cannot get cwd: {}", e)))?;
+    let git_root = find_git_root(&cwd);
+    resolve_schema_from(&cwd, git_root.as_deref())
+}
+
+/// Resolve the schema, ignoring where it came from.
+pub fn resolve_schema() -> Result<GripeSchema, GripeError> {
+    resolve_schema_with_source().map(|(schema, _)| schema)
+}
+
+/// Resolve from `cwd` inside the checkout rooted at `git_root`, if any.
+fn resolve_schema_from(
+    cwd: &Path,
+    git_root: Option<&Path>,
+) -> Result<(GripeSchema, SchemaSource), GripeError> {
+    let cwd = cwd.to_path_buf();
 
     // 1. Try gripe.yaml
     if let Some(yaml_path) = find_gripe_yaml(&cwd) {
@@ -40,18 +93,18 @@ pub fn resolve_schema() -> Result<GripeSchema, String> {
         if schema.repo.is_none() {
             schema.repo = detect_repo(&cwd);
         }
-        return Ok(schema);
+        return Ok((schema, SchemaSource::GripeYaml(yaml_path)));
     }
 
     // 2. Try .github/ISSUE_TEMPLATE
-    if let Some(git_root) = find_git_root(&cwd) {
+    if let Some(git_root) = git_root {
         let templates_dir = git_root.join(".github").join("ISSUE_TEMPLATE");
         if templates_dir.is_dir() {
             if let Some(mut schema) = load_github_templates(&templates_dir) {
                 if schema.repo.is_none() {
                     schema.repo = detect_repo(&cwd);
                 }
-                return Ok(schema);
+                return Ok((schema, SchemaSource::IssueTemplates(templates_dir)));
             }
         }
     }
@@ -59,5 +112,232 @@ pub fn resolve_schema() -> Result<GripeSchema, String> {
     // 3. Built-in default
     let mut schema = default_schema();
     schema.repo = detect_repo(&cwd);
-    Ok(schema)
+    Ok((schema, SchemaSource::Default))
+}
+
+/// Resolve the schema published by a remote repo, following the same chain
+/// as [`resolve_schema_with_source`]: its gripe.yaml, then its issue forms. Returns `None`
+/// when the repo publishes neither.
+pub fn resolve_remote_schema(
+    repo: &str,
+    refresh: bool,
+) -> Result<Option<(GripeSchema, SchemaSource)>, GripeError> {
+    resolve_remote_schema_with(
+        repo,
+        |path| gh::fetch_remote_file(repo, path, refresh),
+        |dir| gh::list_remote_dir(repo, dir, refresh),
+    )
+}
+
+/// [`resolve_remote_schema`] with the GitHub lookups passed in: `fetch`
+/// returns a file's contents (`None` if absent) and `list` a directory's files.
+fn resolve_remote_schema_with<F, L>(
+    repo: &str,
+    mut fetch: F,
+    list: L,
+) -> Result<Option<(GripeSchema, SchemaSource)>, GripeError>
+where
+    F: FnMut(&str) -> Result<Option<String>, GripeError>,
+    L: FnOnce(&str) -> Result<Vec<String>, GripeError>,
+{
+    if let Some(contents) = fetch("gripe.yaml")? {
+        let mut schema: GripeSchema = serde_yaml::from_str(&contents).map_err(|e| {
+            GripeError::ConfigParse(format!("Failed to parse {}:gripe.yaml: {}", repo, e))
+        })?;
+
+        // Template files are relative to the remote gripe.yaml, so fetch them too
+        if let Some(BodyTemplate::File { file }) = &schema.body_template {
+            let template = fetch(file)?.ok_or_else(|| {
+                GripeError::NotFound(format!("Body template {} not found in {}", file, repo))
+            })?;
+            schema.body_template = Some(BodyTemplate::Inline(template));
+        }
+
+        schema.repo = Some(repo.to_string());
+        let source = SchemaSource::Remote(repo.to_string(), "gripe.yaml".to_string());
+        return Ok(Some((schema, source)));
+    }
+
+    let mut paths = list(".github/ISSUE_TEMPLATE")?;
+    paths.sort();
+    for path in paths {
+        if !is_template_file(&path) {
+            continue;
+        }
+        let Some(contents) = fetch(&path)? else {
+            continue;
+        };
+        if let Some(mut schema) = parse_github_template(&contents) {
+            schema.repo = Some(repo.to_string());
+            return Ok(Some((schema, SchemaSource::Remote(repo.to_string(), path))));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Serialize a schema for writing to gripe.yaml, leaving out keys that are
+/// empty or at their defaults so the file stays short enough to edit by hand.
+pub fn schema_to_yaml(schema: &GripeSchema) -> Result<String, GripeError> {
+    let mut value = serde_yaml::to_value(schema)
+        .map_err(|e| GripeError::Other(format!("Failed to serialize schema: {}", e)))?;
+    prune(&mut value);
+
+    if let Value::Mapping(map) = &mut value {
+        let defaults = [
+            ("missing_labels", Value::from("drop")),
+            ("extra_fields", Value::from("ignore")),
+            ("automated", Value::from("allow")),
+            (
+                "redact",
+                serde_yaml::from_str("enabled: true").unwrap_or_default(),
+            ),
+        ];
+        for (key, default) in defaults {
+            if map.get(key) == Some(&default) {
+                map.shift_remove(key);
+            }
+        }
+        for list in ["fields", "comment_fields"] {
+            if let Some(Value::Sequence(fields)) = map.get_mut(list) {
+                for field in fields.iter_mut().filter_map(|f| f.as_mapping_mut()) {
+                    if field.get("required") == Some(&Value::Bool(false)) {
+                        field.shift_remove("required");
+                    }
+                }
+            }
+        }
+        // The one key gripe.yaml can't do without
+        if !map.contains_key("fields") {
+            map.insert("fields".into(), Value::Sequence(Vec::new()));
+        }
+    }
+
+    serde_yaml::to_string(&value)
+        .map_err(|e| GripeError::Other(format!("Failed to serialize schema: {}", e)))
+}
+
+/// Drop nulls and empty lists and tables, innermost first.
+fn prune(value: &mut Value) {
+    match value {
+        Value::Mapping(map) => {
+            for (_, v) in map.iter_mut() {
+                prune(v);
+            }
+            map.retain(|_, v| !is_empty(v));
+        }
+        Value::Sequence(items) => items.iter_mut().for_each(prune),
+        _ => {}
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Sequence(items) => items.is_empty(),
+        Value::Mapping(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const FORM: &str =
+        "name: Bug\nbody:\n  - { type: input, id: what, attributes: { label: What } }\n";
+
+    /// Resolve against an in-memory repo of `path -> contents`.
+    fn resolve_remote(files: &[(&str, &str)]) -> Option<(GripeSchema, SchemaSource)> {
+        let files: HashMap<&str, &str> = files.iter().copied().collect();
+        let listing: Vec<String> = files
+            .keys()
+            .filter(|p| p.starts_with(".github/ISSUE_TEMPLATE/"))
+            .map(|p| p.to_string())
+            .collect();
+        resolve_remote_schema_with(
+            "o/r",
+            |path| Ok(files.get(path).map(|c| c.to_string())),
+            |_| Ok(listing),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_remote_gripe_yaml_wins_over_forms() {
+        let (schema, source) = resolve_remote(&[
+            ("gripe.yaml", "name: cli\nfields: [{ id: summary }]\n"),
+            (".github/ISSUE_TEMPLATE/bug.yml", FORM),
+        ])
+        .unwrap();
+        assert_eq!(schema.name.as_deref(), Some("cli"));
+        assert_eq!(schema.repo.as_deref(), Some("o/r"));
+        assert!(matches!(source, SchemaSource::Remote(_, p) if p == "gripe.yaml"));
+    }
+
+    #[test]
+    fn test_remote_falls_back_to_forms() {
+        let (schema, source) = resolve_remote(&[
+            (
+                ".github/ISSUE_TEMPLATE/config.yml",
+                "blank_issues_enabled: false\n",
+            ),
+            (".github/ISSUE_TEMPLATE/README.md", "# forms"),
+            (".github/ISSUE_TEMPLATE/bug.yml", FORM),
+        ])
+        .unwrap();
+        assert_eq!(schema.fields[0].id, "what");
+        assert!(
+            matches!(source, SchemaSource::Remote(_, p) if p == ".github/ISSUE_TEMPLATE/bug.yml")
+        );
+        assert!(resolve_remote(&[("README.md", "hi")]).is_none());
+    }
+
+    #[test]
+    fn test_remote_errors_propagate() {
+        let result = resolve_remote_schema_with(
+            "o/r",
+            |_| Err(GripeError::Auth("Bad credentials".to_string())),
+            |_| Ok(Vec::new()),
+        );
+        assert!(matches!(result, Err(GripeError::Auth(_))));
+    }
+
+    /// A scratch directory, removed even if the test fails.
+    struct TempDir(PathBuf);
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_local_precedence() {
+        let root =
+            TempDir(env::temp_dir().join(format!("gripe-config-test-{}", std::process::id())));
+        let sub = root.0.join("src");
+        let forms = root.0.join(".github").join("ISSUE_TEMPLATE");
+        std::fs::create_dir_all(&sub).unwrap();
+        std::fs::create_dir_all(&forms).unwrap();
+        std::fs::write(forms.join("bug.yml"), FORM).unwrap();
+        std::fs::write(root.0.join("gripe.yaml"), "fields: [{ id: summary }]\n").unwrap();
+
+        let (_, source) = resolve_schema_from(&sub, Some(&root.0)).unwrap();
+        assert!(matches!(source, SchemaSource::GripeYaml(_)));
+
+        std::fs::remove_file(root.0.join("gripe.yaml")).unwrap();
+        let (schema, source) = resolve_schema_from(&sub, Some(&root.0)).unwrap();
+        assert!(matches!(source, SchemaSource::IssueTemplates(_)));
+        assert_eq!(schema.fields[0].id, "what");
+
+        // Outside a checkout the issue forms aren't looked for
+        let (_, source) = resolve_schema_from(&sub, None).unwrap();
+        assert!(matches!(source, SchemaSource::Default));
+
+        std::fs::remove_dir_all(&forms).unwrap();
+        let (_, source) = resolve_schema_from(&sub, Some(&root.0)).unwrap();
+        assert!(matches!(source, SchemaSource::Default));
+    }
 }
@@ -1,47 +1,85 @@
-use crate::types::{AutomatedPolicy, FieldDefinition, FieldType, GripeSchema};
+use std::collections::BTreeMap;
+
+use crate::types::{
+    AutomatedMode, AutomatedPolicy, ExtraFields, FieldDefinition, FieldType, GripeSchema,
+    MissingLabels, RedactConfig,
+};
+
+/// Starter schemas offered by `gripe init --preset`.
+pub const PRESETS: &[&str] = &["bug", "feature", "agent-feedback", "security"];
 
 pub fn default_schema() -> GripeSchema {
     GripeSchema {
+        name: None,
         repo: None,
-        automated: AutomatedPolicy::Allow,
+        automated: AutomatedPolicy::default(),
         labels: vec!["feedback".to_string()],
+        labels_from: BTreeMap::new(),
+        missing_labels: MissingLabels::default(),
+        label_definitions: BTreeMap::new(),
+        assignees: vec![],
+        milestone: None,
+        projects: vec![],
+        issue_type: None,
+        routing: vec![],
+        codeowners: None,
         title_template: Some("[{tool}] {summary}".to_string()),
+        body_template: None,
+        redact: RedactConfig::default(),
+        extra_fields: ExtraFields::default(),
         fields: vec![
             FieldDefinition {
                 id: "tool".to_string(),
                 label: Some("Tool Name".to_string()),
+                description: None,
                 field_type: FieldType::Input,
                 required: true,
                 options: vec![],
                 default: None,
+                pattern: None,
+                min: None,
+                max: None,
             },
             FieldDefinition {
                 id: "summary".to_string(),
                 label: Some("Summary".to_string()),
+                description: None,
                 field_type: FieldType::Input,
                 required: true,
                 options: vec![],
                 default: None,
+                pattern: None,
+                min: None,
+                max: None,
             },
             FieldDefinition {
                 id: "expected".to_string(),
                 label: Some("Expected Behavior".to_string()),
+                description: None,
                 field_type: FieldType::Textarea,
                 required: true,
                 options: vec![],
                 default: None,
+                pattern: None,
+                min: None,
+                max: None,
             },
             FieldDefinition {
                 id: "actual".to_string(),
                 label: Some("Actual Behavior".to_string()),
+                description: None,
                 field_type: FieldType::Textarea,
                 required: true,
                 options: vec![],
                 default: None,
+                pattern: None,
+                min: None,
+                max: None,
             },
             FieldDefinition {
                 id: "severity".to_string(),
                 label: Some("Severity".to_string()),
+                description: None,
                 field_type: FieldType::Select,
                 required: false,
                 options: vec![
@@ -51,15 +89,164 @@ pub fn default_schema() -> GripeSchema {
                     "critical".to_string(),
                 ],
                 default: Some("medium".to_string()),
+                pattern: None,
+                min: None,
+                max: None,
             },
             FieldDefinition {
                 id: "context".to_string(),
                 label: Some("Additional Context".to_string()),
+                description: None,
                 field_type: FieldType::Textarea,
                 required: false,
                 options: vec![],
                 default: None,
+                pattern: None,
+                min: None,
+                max: None,
             },
         ],
+        comment_fields: vec![],
+    }
+}
+
+/// The starter schema called `name`, one of [`PRESETS`].
+pub fn preset(name: &str) -> Option<GripeSchema> {
+    let schema = match name {
+        "bug" => GripeSchema {
+            name: Some("bug".to_string()),
+            labels: vec!["bug".to_string()],
+            title_template: Some("[Bug] {summary}".to_string()),
+            fields: vec![
+                field("summary", "Summary", FieldType::Input, true),
+                field("steps", "Steps to Reproduce", FieldType::Textarea, true),
+                field("expected", "Expected Behavior", FieldType::Textarea, true),
+                field("actual", "Actual Behavior", FieldType::Textarea, true),
+                field("version", "Version", FieldType::Input, false),
+                severity(),
+                field("logs", "Logs", FieldType::Textarea, false),
+            ],
+            ..default_schema()
+        },
+        "feature" => GripeSchema {
+            name: Some("feature".to_string()),
+            labels: vec!["enhancement".to_string()],
+            title_template: Some("[Feature] {summary}".to_string()),
+            fields: vec![
+                field("summary", "Summary", FieldType::Input, true),
+                field("problem", "Problem", FieldType::Textarea, true),
+                field("proposal", "Proposed Solution", FieldType::Textarea, true),
+                field(
+                    "alternatives",
+                    "Alternatives Considered",
+                    FieldType::Textarea,
+                    false,
+                ),
+                FieldDefinition {
+                    options: strings(&["nice-to-have", "important", "critical"]),
+                    default: Some("nice-to-have".to_string()),
+                    ..field("priority", "Priority", FieldType::Select, false)
+                },
+            ],
+            ..default_schema()
+        },
+        // Filed by tools and agents rather than people, so extra context is
+        // kept and every submission must say who sent it
+        "agent-feedback" => GripeSchema {
+            name: Some("agent-feedback".to_string()),
+            labels: vec!["agent-feedback".to_string()],
+            automated: AutomatedPolicy {
+                rate_limit: Some(20),
+                labels: vec!["automated".to_string()],
+                require_reporter: true,
+                ..AutomatedPolicy::default()
+            },
+            extra_fields: ExtraFields::Append,
+            fields: vec![
+                field("tool", "Tool Name", FieldType::Input, true),
+                field("summary", "Summary", FieldType::Input, true),
+                field("expected", "Expected Behavior", FieldType::Textarea, true),
+                field("actual", "Actual Behavior", FieldType::Textarea, true),
+                field("steps", "Steps Taken", FieldType::List, false),
+                severity(),
+                field("context", "Additional Context", FieldType::Textarea, false),
+            ],
+            ..default_schema()
+        },
+        "security" => GripeSchema {
+            name: Some("security".to_string()),
+            labels: vec!["security".to_string()],
+            automated: AutomatedPolicy::with_mode(AutomatedMode::Deny),
+            title_template: Some("[Security] {summary}".to_string()),
+            fields: vec![
+                field("summary", "Summary", FieldType::Input, true),
+                field("component", "Affected Component", FieldType::Input, true),
+                FieldDefinition {
+                    required: true,
+                    default: None,
+                    ..severity()
+                },
+                field("details", "Details", FieldType::Textarea, true),
+                field("reproduction", "Reproduction", FieldType::Textarea, false),
+                FieldDefinition {
+                    description: Some(
+                        "Exploitable vulnerabilities belong in a private security advisory"
+                            .to_string(),
+                    ),
+                    ..field(
+                        "not_exploitable",
+                        "This report contains no exploit details",
+                        FieldType::Checkbox,
+                        true,
+                    )
+                },
+            ],
+            ..default_schema()
+        },
+        _ => return None,
+    };
+    Some(schema)
+}
+
+fn field(id: &str, label: &str, field_type: FieldType, required: bool) -> FieldDefinition {
+    FieldDefinition {
+        id: id.to_string(),
+        label: Some(label.to_string()),
+        description: None,
+        field_type,
+        required,
+        options: vec![],
+        default: None,
+        pattern: None,
+        min: None,
+        max: None,
+    }
+}
+
+fn severity() -> FieldDefinition {
+    FieldDefinition {
+        options: strings(&["low", "medium", "high", "critical"]),
+        default: Some("medium".to_string()),
+        ..field("severity", "Severity", FieldType::Select, false)
+    }
+}
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema_to_yaml;
+    use crate::lint::lint;
+
+    #[test]
+    fn test_presets_are_valid() {
+        for name in PRESETS {
+            let yaml = schema_to_yaml(&preset(name).unwrap()).unwrap();
+            assert_eq!(lint(&yaml), vec![], "preset {}", name);
+        }
+        assert!(preset("nope").is_none());
     }
 }
@@ -0,0 +1,131 @@
+use std::fmt;
+
+/// An error that ends a gripe command, classified so scripts can react to it.
+///
+/// Each kind has its own exit code (see [`GripeError::exit_code`]); clap
+/// usage errors keep clap's exit code 2.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GripeError {
+    /// Field values, arguments or attachments were rejected.
+    Validation(String),
+    /// An automated-submission policy refused the submission.
+    PolicyDenied(String),
+    /// GitHub credentials are missing, invalid or lack permission.
+    Auth(String),
+    /// GitHub could not be reached, or asked us to back off.
+    Network(String),
+    /// The repository, issue or file doesn't exist.
+    NotFound(String),
+    /// gripe.yaml or one of its templates could not be read or parsed.
+    ConfigParse(String),
+    /// Anything else (local I/O, unexpected responses).
+    Other(String),
+}
+
+impl GripeError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            GripeError::Other(_) => 1,
+            GripeError::Validation(_) => 3,
+            GripeError::ConfigParse(_) => 4,
+            GripeError::PolicyDenied(_) => 5,
+            GripeError::Auth(_) => 6,
+            GripeError::NotFound(_) => 7,
+            GripeError::Network(_) => 8,
+        }
+    }
+
+    /// The machine-readable name used in JSON error output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GripeError::Validation(_) => "validation",
+            GripeError::PolicyDenied(_) => "policy_denied",
+            GripeError::Auth(_) => "auth",
+            GripeError::Network(_) => "network",
+            GripeError::NotFound(_) => "not_found",
+            GripeError::ConfigParse(_) => "config_parse",
+            GripeError::Other(_) => "error",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            GripeError::Validation(m)
+            | GripeError::PolicyDenied(m)
+            | GripeError::Auth(m)
+            | GripeError::Network(m)
+            | GripeError::NotFound(m)
+            | GripeError::ConfigParse(m)
+            | GripeError::Other(m) => m,
+        }
+    }
+
+    /// An error of the same kind with a new message, for adding context.
+    pub fn with_message(&self, message: String) -> Self {
+        match self {
+            GripeError::Validation(_) => GripeError::Validation(message),
+            GripeError::PolicyDenied(_) => GripeError::PolicyDenied(message),
+            GripeError::Auth(_) => GripeError::Auth(message),
+            GripeError::Network(_) => GripeError::Network(message),
+            GripeError::NotFound(_) => GripeError::NotFound(message),
+            GripeError::ConfigParse(_) => GripeError::ConfigParse(message),
+            GripeError::Other(_) => GripeError::Other(message),
+        }
+    }
+
+    /// Classify a failed GitHub request by its HTTP status.
+    pub fn from_http(status: u16, message: String) -> Self {
+        match status {
+            // GitHub reports exhausted rate limits as 403
+            403 | 429 if message.to_lowercase().contains("rate limit") => {
+                GripeError::Network(message)
+            }
+            401 | 403 => GripeError::Auth(message),
+            404 | 410 => GripeError::NotFound(message),
+            422 => GripeError::Validation(message),
+            429 | 500..=599 => GripeError::Network(message),
+            _ => GripeError::Other(message),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "error": {
+                "kind": self.kind(),
+                "message": self.message(),
+                "exit_code": self.exit_code(),
+            }
+        })
+    }
+}
+
+impl fmt::Display for GripeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_http() {
+        let err = |status| GripeError::from_http(status, "x".to_string());
+        assert_eq!(err(401), GripeError::Auth("x".into()));
+        assert_eq!(err(404), GripeError::NotFound("x".into()));
+        assert_eq!(err(502), GripeError::Network("x".into()));
+        assert_eq!(
+            GripeError::from_http(403, "API rate limit exceeded".into()).kind(),
+            "network"
+        );
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let json = GripeError::PolicyDenied("no bots".into()).to_json();
+        assert_eq!(json["error"]["kind"], "policy_denied");
+        assert_eq!(json["error"]["message"], "no bots");
+        assert_eq!(json["error"]["exit_code"], 5);
+    }
+}
@@ -1,60 +1,356 @@
-use std::process::Command;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+use std::sync::OnceLock;
 
 use colored::Colorize;
+use serde::Serialize;
 
-use crate::types::AutomatedPolicy;
+use crate::cache::{self, CachedFile};
+use crate::error::GripeError;
+use crate::github_api::{
+    api_base_url, describe_error, env_token, graphql_error, ApiClient, RawResponse,
+};
+use crate::policy::FiledTo;
+use crate::types::{deserialize_policy, AutomatedPolicy, LabelDefinition, MissingLabels};
 
 pub struct IssueResult {
     pub url: String,
     pub number: u64,
 }
 
-pub fn check_gh_available() -> Result<(), String> {
-    Command::new("gh")
-        .args(["--version"])
-        .output()
-        .map_err(|_| "gh CLI not found. Install it: https://cli.github.com".to_string())?;
-    Ok(())
+/// An issue found by [`search_my_issues`].
+#[derive(Debug, Clone)]
+pub struct IssueSummary {
+    pub repo: String,
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+    /// `open` or `closed`.
+    pub state: String,
+    pub labels: Vec<String>,
+    pub body: String,
+    pub created_at: String,
 }
 
-/// Check if the target repo allows automated submissions.
-/// Fetches gripe.yaml from the target repo via gh api.
-pub fn check_robots(repo: &str) -> Result<AutomatedPolicy, String> {
-    let output = Command::new("gh")
-        .args([
-            "api",
-            &format!("repos/{}/contents/gripe.yaml", repo),
-            "--jq",
-            ".content",
-        ])
-        .output()
-        .map_err(|e| format!("Failed to run gh: {}", e))?;
+/// Filters for [`search_my_issues`].
+#[derive(Debug, Clone, Default)]
+pub struct IssueQuery {
+    pub repo: Option<String>,
+    /// `open` or `closed`; `None` for both.
+    pub state: Option<String>,
+    pub labels: Vec<String>,
+    pub limit: usize,
+}
 
-    if !output.status.success() {
-        // No gripe.yaml in target repo — allow by default
-        return Ok(AutomatedPolicy::Allow);
+/// Everything besides the title and body that an issue is filed with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IssueMeta {
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    /// Milestone title.
+    pub milestone: Option<String>,
+    /// Projects as `owner/number`.
+    pub projects: Vec<String>,
+    pub issue_type: Option<String>,
+}
+
+/// An issue's current state, as shown by `gripe status`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IssueStatus {
+    pub repo: String,
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+    /// `open` or `closed`.
+    pub state: String,
+    /// Why a closed issue was closed: `completed`, `not_planned` or `duplicate`.
+    pub state_reason: Option<String>,
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    /// Pull requests linked to the issue (those that close it when merged).
+    pub linked_prs: Vec<LinkedPr>,
+    /// The latest comments, oldest first.
+    pub comments: Vec<IssueComment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinkedPr {
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+    /// `open`, `closed` or `merged`.
+    pub state: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IssueComment {
+    pub author: String,
+    pub created_at: String,
+    pub body: String,
+}
+
+/// How gripe reaches GitHub: the `gh` CLI, or the API directly with a token.
+enum Backend {
+    Cli,
+    Api(ApiClient),
+}
+
+/// The backend for this process, chosen once.
+///
+/// `GRIPE_BACKEND=gh|api` forces a choice; otherwise gh is used when installed
+/// and the API when a `GH_TOKEN`/`GITHUB_TOKEN` is set.
+fn backend() -> Result<&'static Backend, GripeError> {
+    static BACKEND: OnceLock<Result<Backend, GripeError>> = OnceLock::new();
+    BACKEND
+        .get_or_init(select_backend)
+        .as_ref()
+        .map_err(|e| e.clone())
+}
+
+fn select_backend() -> Result<Backend, GripeError> {
+    let api = || env_token().map(|token| Backend::Api(ApiClient::new(&api_base_url(), &token)));
+    let gh_installed = || {
+        Command::new("gh")
+            .arg("--version")
+            .output()
+            .is_ok_and(|o| o.status.success())
+    };
+
+    match std::env::var("GRIPE_BACKEND").ok().as_deref() {
+        Some("api") => api().ok_or_else(|| {
+            GripeError::Auth(
+                "GRIPE_BACKEND=api requires GH_TOKEN or GITHUB_TOKEN to be set".to_string(),
+            )
+        }),
+        Some("gh") if gh_installed() => Ok(Backend::Cli),
+        // A missing binary is a setup problem, not a credentials one
+        Some("gh") => Err(GripeError::Other(
+            "gh CLI not found. Install it: https://cli.github.com".to_string(),
+        )),
+        Some(other) => Err(GripeError::Validation(format!(
+            "Unknown GRIPE_BACKEND '{}'. Expected gh or api.",
+            other
+        ))),
+        None if gh_installed() => Ok(Backend::Cli),
+        None => api().ok_or_else(|| {
+            GripeError::Other("gh CLI not found. Install it (https://cli.github.com) or set GITHUB_TOKEN to use the GitHub API directly.".to_string())
+        }),
     }
+}
 
-    let encoded = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    if encoded.is_empty() {
-        return Ok(AutomatedPolicy::Allow);
+/// Check that GitHub is reachable through gh or a token.
+pub fn check_gh_available() -> Result<(), GripeError> {
+    backend().map(|_| ())
+}
+
+/// Check if the target repo allows automated submissions.
+/// Fetches gripe.yaml from the target repo via gh api. Returns `None` when
+/// the repo has no gripe.yaml.
+///
+/// A gripe.yaml or `automated:` block that doesn't parse is an error rather
+/// than the default `allow`, so a typo in a deny policy can't let agents in.
+pub fn check_robots(repo: &str, refresh: bool) -> Result<Option<AutomatedPolicy>, GripeError> {
+    let decoded = match fetch_remote_gripe_yaml(repo, refresh)? {
+        Some(d) => d,
+        None => return Ok(None),
+    };
+    parse_remote_policy(repo, &decoded).map(Some)
+}
+
+fn parse_remote_policy(repo: &str, contents: &str) -> Result<AutomatedPolicy, GripeError> {
+    let invalid = |e: serde_yaml::Error| {
+        GripeError::ConfigParse(format!(
+            "{}'s gripe.yaml has an invalid automated policy: {}",
+            repo, e
+        ))
+    };
+
+    let val = serde_yaml::from_str::<serde_yaml::Value>(contents).map_err(invalid)?;
+    match val.get("automated") {
+        Some(automated) => deserialize_policy(automated.clone()).map_err(invalid),
+        None => Ok(AutomatedPolicy::default()),
     }
+}
+
+/// Fetch the target repo's gripe.yaml contents.
+pub fn fetch_remote_gripe_yaml(repo: &str, refresh: bool) -> Result<Option<String>, GripeError> {
+    fetch_remote_file(repo, "gripe.yaml", refresh)
+}
+
+/// Fetch and decode a file from the target repo's default branch.
+pub fn fetch_remote_file(
+    repo: &str,
+    path: &str,
+    refresh: bool,
+) -> Result<Option<String>, GripeError> {
+    let json = match get_contents(repo, path, refresh)? {
+        Some(json) => json,
+        None => return Ok(None),
+    };
 
     // GitHub API returns base64-encoded content (with possible newlines)
-    let cleaned: String = encoded.chars().filter(|c| !c.is_whitespace()).collect();
-    let decoded =
-        base64_decode(&cleaned).map_err(|e| format!("Failed to decode content: {}", e))?;
-
-    match serde_yaml::from_str::<serde_yaml::Value>(&decoded) {
-        Ok(val) => {
-            if let Some(automated) = val.get("automated").and_then(|v| v.as_str()) {
-                if automated == "deny" {
-                    return Ok(AutomatedPolicy::Deny);
+    let cleaned: String = json["content"]
+        .as_str()
+        .unwrap_or("")
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    base64_decode(&cleaned)
+        .map(Some)
+        .map_err(|e| GripeError::Other(format!("Failed to decode {} from {}: {}", path, repo, e)))
+}
+
+/// List the file paths in a directory of the target repo.
+pub fn list_remote_dir(repo: &str, path: &str, refresh: bool) -> Result<Vec<String>, GripeError> {
+    let json = match get_contents(repo, path, refresh)? {
+        Some(json) => json,
+        None => return Ok(Vec::new()),
+    };
+
+    Ok(json
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry["type"] == "file")
+        .filter_map(|entry| entry["path"].as_str().map(|p| p.to_string()))
+        .collect())
+}
+
+/// GET `repos/{repo}/contents/{path}`, returning `None` if it doesn't exist.
+///
+/// Responses are cached on disk per repo and path. A fresh entry is used
+/// as-is; a stale one is revalidated with `If-None-Match`, so an unchanged
+/// file costs a 304 that doesn't count against the rate limit. `refresh`
+/// bypasses the cache entirely.
+fn get_contents(
+    repo: &str,
+    path: &str,
+    refresh: bool,
+) -> Result<Option<serde_json::Value>, GripeError> {
+    let key = format!("{}/repos/{}/contents/{}", api_host()?, repo, path);
+    let cached = if refresh { None } else { cache::load(&key) };
+
+    let body = match cached.as_ref().filter(|e| e.is_fresh()) {
+        Some(entry) => entry.content.clone(),
+        None => {
+            let etag = cached.as_ref().and_then(|e| e.etag.as_deref());
+            let response = fetch_contents(repo, path, etag)?;
+
+            match response.status {
+                304 => match cached {
+                    Some(mut entry) => {
+                        entry.touch();
+                        cache::store(&key, &entry);
+                        entry.content
+                    }
+                    None => None,
+                },
+                200 => {
+                    cache::store(
+                        &key,
+                        &CachedFile::new(response.etag, Some(response.body.clone())),
+                    );
+                    Some(response.body)
                 }
+                404 => {
+                    cache::store(&key, &CachedFile::new(None, None));
+                    None
+                }
+                // Only a 404 means the file is absent. Anything else, such as
+                // bad auth or a rate limit, must not pass for "no policy"
+                _ => return Err(contents_error(repo, path, &response)),
             }
-            Ok(AutomatedPolicy::Allow)
         }
-        Err(_) => Ok(AutomatedPolicy::Allow),
+    };
+
+    match body {
+        Some(body) => serde_json::from_str(&body)
+            .map(Some)
+            .map_err(|e| GripeError::Other(format!("Failed to parse gh output: {}", e))),
+        None => Ok(None),
+    }
+}
+
+/// The API the current backend talks to, so cached responses from one
+/// GitHub host are never served for another.
+fn api_host() -> Result<String, GripeError> {
+    Ok(match backend()? {
+        Backend::Api(api) => api.base_url().to_string(),
+        Backend::Cli => std::env::var("GH_HOST")
+            .ok()
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| "github.com".to_string()),
+    })
+}
+
+fn contents_error(repo: &str, path: &str, response: &RawResponse) -> GripeError {
+    GripeError::from_http(
+        response.status,
+        format!(
+            "Fetching {} from {} failed: {}",
+            path,
+            repo,
+            describe_error(response)
+        ),
+    )
+}
+
+/// Fetch a contents API path without the cache.
+fn fetch_contents(repo: &str, path: &str, etag: Option<&str>) -> Result<RawResponse, GripeError> {
+    if let Backend::Api(api) = backend()? {
+        return api.get_contents(repo, path, etag);
+    }
+
+    let mut args = vec![
+        "api".to_string(),
+        "--include".to_string(),
+        format!("repos/{}/contents/{}", repo, path),
+    ];
+    if let Some(etag) = etag {
+        args.push("--header".to_string());
+        args.push(format!("If-None-Match: {}", etag));
+    }
+
+    let output = Command::new("gh")
+        .args(&args)
+        .output()
+        .map_err(spawn_error)?;
+    let response = parse_include_output(&String::from_utf8_lossy(&output.stdout));
+    // No status line means gh never got a response (no auth, no connection)
+    if response.status == 0 {
+        return Err(gh_failure(
+            "gh api",
+            String::from_utf8_lossy(&output.stderr).trim(),
+        ));
+    }
+    Ok(response)
+}
+
+/// Split `gh api --include` output into status, ETag and body.
+fn parse_include_output(output: &str) -> RawResponse {
+    let normalized = output.replace("\r\n", "\n");
+    let (head, body) = normalized
+        .split_once("\n\n")
+        .unwrap_or((normalized.as_str(), ""));
+    let mut lines = head.lines();
+
+    let status = lines
+        .next()
+        .and_then(|l| l.split_whitespace().nth(1))
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
+    let etag = lines.find_map(|l| {
+        let (name, value) = l.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("etag")
+            .then(|| value.trim().to_string())
+    });
+
+    RawResponse {
+        status,
+        etag,
+        body: body.to_string(),
     }
 }
 
@@ -96,35 +392,726 @@ fn base64_decode(input: &str) -> Result<String, String> {
     String::from_utf8(output).map_err(|e| format!("Invalid UTF-8: {}", e))
 }
 
+/// Run gh with `input` piped to its stdin.
+fn run_gh_with_input(args: &[String], input: &str) -> Result<Output, GripeError> {
+    let mut child = Command::new("gh")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(spawn_error)?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| GripeError::Other(format!("Failed to write to gh: {}", e)))?;
+    }
+
+    child.wait_with_output().map_err(spawn_error)
+}
+
+fn spawn_error(e: std::io::Error) -> GripeError {
+    GripeError::Other(format!("Failed to run gh: {}", e))
+}
+
+/// Classify a failed gh command from its stderr.
+///
+/// gh prints `HTTP <status>` for API errors; otherwise fall back to the
+/// messages it uses for auth and connection problems.
+fn gh_failure(command: &str, stderr: &str) -> GripeError {
+    let message = format!("{} failed: {}", command, stderr);
+    let status = stderr
+        .split("HTTP ")
+        .nth(1)
+        .and_then(|rest| rest.get(..3))
+        .and_then(|code| code.parse().ok());
+    if let Some(status) = status {
+        return GripeError::from_http(status, message);
+    }
+
+    let lower = stderr.to_lowercase();
+    if lower.contains("gh auth login") || lower.contains("authentication") {
+        GripeError::Auth(message)
+    } else if lower.contains("could not resolve to a")
+        || lower.contains("not found")
+        || lower.contains("no such")
+    {
+        GripeError::NotFound(message)
+    } else if [
+        "error connecting",
+        "dial tcp",
+        "timeout",
+        "connection refused",
+    ]
+    .iter()
+    .any(|p| lower.contains(p))
+    {
+        GripeError::Network(message)
+    } else {
+        GripeError::Other(message)
+    }
+}
+
+/// Upload content as a secret gist and return its URL.
+///
+/// Content is piped through stdin so the uploaded text is exactly what gripe
+/// rendered (e.g. after redaction), not the file on disk.
+pub fn create_gist(filename: &str, content: &str, description: &str) -> Result<String, GripeError> {
+    if let Backend::Api(api) = backend()? {
+        return api.create_gist(filename, content, description);
+    }
+
+    let args: Vec<String> = [
+        "gist",
+        "create",
+        "--filename",
+        filename,
+        "--desc",
+        description,
+        "-",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let output = run_gh_with_input(&args, content)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(gh_failure("gh gist create", &stderr));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Post a comment on an existing issue, returning the comment's URL.
+pub fn add_comment(repo: &str, number: u64, body: &str) -> Result<String, GripeError> {
+    if let Backend::Api(api) = backend()? {
+        return api.add_comment(repo, number, body);
+    }
+
+    let args: Vec<String> = [
+        "issue",
+        "comment",
+        &number.to_string(),
+        "--repo",
+        repo,
+        "--body-file",
+        "-",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let output = run_gh_with_input(&args, body)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(gh_failure("gh issue comment", &stderr));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Count what the current user filed at `target` on or after `since`
+/// (`YYYY-MM-DD`). Counts by author rather than label, since labels may
+/// have been dropped when filing.
+pub fn count_my_submissions_since(target: &FiledTo, since: &str) -> Result<usize, GripeError> {
+    let repo = match target {
+        FiledTo::Issues(repo) => repo,
+        FiledTo::Discussions { repo, .. } => {
+            let query = format!("repo:{} author:@me created:>={}", repo, since);
+            let json = graphql(
+                "query($q: String!) { search(query: $q, type: DISCUSSION, first: 1) { discussionCount } }",
+                serde_json::json!({ "q": query }),
+            )?;
+            return Ok(json["data"]["search"]["discussionCount"]
+                .as_u64()
+                .unwrap_or(0) as usize);
+        }
+    };
+
+    if let Backend::Api(api) = backend()? {
+        let query = format!("repo:{} is:issue author:@me created:>={}", repo, since);
+        return api.search_issue_count(&query);
+    }
+
+    let args = [
+        "issue",
+        "list",
+        "--repo",
+        repo,
+        "--state",
+        "all",
+        "--author",
+        "@me",
+        "--search",
+        &format!("created:>={}", since),
+        "--json",
+        "number",
+        "--limit",
+        "1000",
+    ];
+
+    let output = Command::new("gh")
+        .args(args)
+        .output()
+        .map_err(spawn_error)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(gh_failure("gh issue list", &stderr));
+    }
+
+    let issues: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)
+        .map_err(|e| GripeError::Other(format!("Failed to parse gh output: {}", e)))?;
+    Ok(issues.len())
+}
+
+/// Issues opened by the current user, newest first.
+pub fn search_my_issues(query: &IssueQuery) -> Result<Vec<IssueSummary>, GripeError> {
+    if let Backend::Api(api) = backend()? {
+        let mut q = "is:issue author:@me".to_string();
+        if let Some(repo) = &query.repo {
+            q.push_str(&format!(" repo:{}", repo));
+        }
+        if let Some(state) = &query.state {
+            q.push_str(&format!(" state:{}", state));
+        }
+        for label in &query.labels {
+            q.push_str(&format!(" label:\"{}\"", label));
+        }
+        return api.search_issues(&q, query.limit);
+    }
+
+    let mut args = vec![
+        "search".to_string(),
+        "issues".to_string(),
+        "--author".to_string(),
+        "@me".to_string(),
+        "--sort".to_string(),
+        "created".to_string(),
+        "--order".to_string(),
+        "desc".to_string(),
+        "--limit".to_string(),
+        query.limit.to_string(),
+        "--json".to_string(),
+        "repository,number,title,url,state,labels,body,createdAt".to_string(),
+    ];
+    if let Some(repo) = &query.repo {
+        args.push("--repo".to_string());
+        args.push(repo.to_string());
+    }
+    if let Some(state) = &query.state {
+        args.push("--state".to_string());
+        args.push(state.to_string());
+    }
+    for label in &query.labels {
+        args.push("--label".to_string());
+        args.push(label.to_string());
+    }
+
+    let output = Command::new("gh")
+        .args(&args)
+        .output()
+        .map_err(spawn_error)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(gh_failure("gh search issues", &stderr));
+    }
+
+    let issues: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)
+        .map_err(|e| GripeError::Other(format!("Failed to parse gh output: {}", e)))?;
+    Ok(issues
+        .iter()
+        .map(|issue| IssueSummary {
+            repo: issue["repository"]["nameWithOwner"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            number: issue["number"].as_u64().unwrap_or(0),
+            title: issue["title"].as_str().unwrap_or_default().to_string(),
+            url: issue["url"].as_str().unwrap_or_default().to_string(),
+            state: issue["state"].as_str().unwrap_or_default().to_lowercase(),
+            labels: label_names(&issue["labels"]),
+            body: issue["body"].as_str().unwrap_or_default().to_string(),
+            created_at: issue["createdAt"].as_str().unwrap_or_default().to_string(),
+        })
+        .collect())
+}
+
+/// Names from a JSON array of label objects.
+pub fn label_names(labels: &serde_json::Value) -> Vec<String> {
+    labels
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|l| l["name"].as_str().map(|n| n.to_string()))
+        .collect()
+}
+
+/// Parse an issue reference (`42`, `#42`, `owner/repo#42` or an issue URL)
+/// into its repo and number. Bare numbers need `default_repo`.
+pub fn parse_issue_ref(
+    reference: &str,
+    default_repo: Option<&str>,
+) -> Result<(String, u64), GripeError> {
+    let reference = reference.trim();
+    let invalid = || {
+        GripeError::Validation(format!(
+            "Invalid issue '{}'. Use a number, owner/repo#number or an issue URL.",
+            reference
+        ))
+    };
+
+    if let Some((_, path)) = reference.split_once("://") {
+        // github.com/owner/repo/issues/42 (pull request URLs work too)
+        let path = path.split(['#', '?']).next().unwrap_or("");
+        let parts: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+        return match parts.as_slice() {
+            [_, owner, repo, "issues" | "pull", number, ..] => Ok((
+                format!("{}/{}", owner, repo),
+                number.parse().map_err(|_| invalid())?,
+            )),
+            _ => Err(invalid()),
+        };
+    }
+
+    let (repo, number) = match reference.rsplit_once('#') {
+        Some((repo, number)) if !repo.is_empty() => (Some(repo.to_string()), number),
+        Some((_, number)) => (None, number),
+        None => (None, reference),
+    };
+    let number = number.parse().map_err(|_| invalid())?;
+    let repo = repo
+        .or_else(|| default_repo.map(|r| r.to_string()))
+        .ok_or_else(|| {
+            GripeError::Validation(format!(
+                "No repo for issue #{}. Use --repo, owner/repo#{} or an issue URL.",
+                number, number
+            ))
+        })?;
+    Ok((repo, number))
+}
+
+/// Fetch an issue's state, labels, assignees, linked pull requests and its
+/// latest `comments` comments.
+pub fn issue_status(repo: &str, number: u64, comments: usize) -> Result<IssueStatus, GripeError> {
+    let (owner, name) = repo.split_once('/').ok_or_else(|| {
+        GripeError::Validation(format!("Invalid repo '{}'. Expected owner/repo.", repo))
+    })?;
+
+    let response = graphql(
+        "query($owner: String!, $name: String!, $number: Int!, $comments: Int!) { repository(owner: $owner, name: $name) { issue(number: $number) { number title url state stateReason labels(first: 100) { nodes { name } } assignees(first: 100) { nodes { login } } closedByPullRequestsReferences(first: 20, includeClosedPrs: true) { nodes { number title url state } } comments(last: $comments) { nodes { author { login } createdAt body } } } } }",
+        serde_json::json!({
+            "owner": owner,
+            "name": name,
+            "number": number,
+            "comments": comments.min(100),
+        }),
+    )?;
+    parse_issue_status(repo, &response["data"]["repository"]["issue"])
+        .ok_or_else(|| GripeError::NotFound(format!("Issue {}#{} not found", repo, number)))
+}
+
+fn parse_issue_status(repo: &str, issue: &serde_json::Value) -> Option<IssueStatus> {
+    let nodes = |connection: &str| {
+        issue[connection]["nodes"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+    };
+    let text = |value: &serde_json::Value| value.as_str().unwrap_or_default().to_string();
+
+    Some(IssueStatus {
+        repo: repo.to_string(),
+        number: issue["number"].as_u64()?,
+        title: text(&issue["title"]),
+        url: text(&issue["url"]),
+        state: issue["state"].as_str()?.to_lowercase(),
+        state_reason: issue["stateReason"].as_str().map(|r| r.to_lowercase()),
+        labels: label_names(&issue["labels"]["nodes"]),
+        assignees: nodes("assignees")
+            .iter()
+            .map(|a| text(&a["login"]))
+            .collect(),
+        linked_prs: nodes("closedByPullRequestsReferences")
+            .iter()
+            .map(|pr| LinkedPr {
+                number: pr["number"].as_u64().unwrap_or(0),
+                title: text(&pr["title"]),
+                url: text(&pr["url"]),
+                state: text(&pr["state"]).to_lowercase(),
+            })
+            .collect(),
+        comments: nodes("comments")
+            .iter()
+            .map(|c| IssueComment {
+                // Deleted accounts have no author
+                author: c["author"]["login"].as_str().unwrap_or("ghost").to_string(),
+                created_at: text(&c["createdAt"]),
+                body: text(&c["body"]),
+            })
+            .collect(),
+    })
+}
+
+/// Run a GraphQL request, passing query and variables on stdin.
+fn graphql(query: &str, variables: serde_json::Value) -> Result<serde_json::Value, GripeError> {
+    if let Backend::Api(api) = backend()? {
+        return api.graphql(query, variables);
+    }
+
+    let request = serde_json::json!({ "query": query, "variables": variables });
+    let args: Vec<String> = ["api", "graphql", "--input", "-"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let output = run_gh_with_input(&args, &request.to_string())?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(gh_failure("gh api graphql", &stderr));
+    }
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| GripeError::Other(format!("Failed to parse gh output: {}", e)))?;
+    match json.get("errors") {
+        Some(errors) => Err(graphql_error(errors)),
+        None => Ok(json),
+    }
+}
+
+/// Open a discussion in `category` of `repo`.
+pub fn create_discussion(
+    repo: &str,
+    category: &str,
+    title: &str,
+    body: &str,
+) -> Result<IssueResult, GripeError> {
+    let (owner, name) = repo.split_once('/').ok_or_else(|| {
+        GripeError::Validation(format!("Invalid repo '{}'. Expected owner/repo.", repo))
+    })?;
+
+    let lookup = graphql(
+        "query($owner: String!, $name: String!) { repository(owner: $owner, name: $name) { id discussionCategories(first: 100) { nodes { id name } } } }",
+        serde_json::json!({ "owner": owner, "name": name }),
+    )?;
+    let repository = &lookup["data"]["repository"];
+    let repo_id = repository["id"]
+        .as_str()
+        .ok_or_else(|| GripeError::NotFound(format!("Repository {} not found", repo)))?;
+    let category_id = repository["discussionCategories"]["nodes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|c| {
+            c["name"]
+                .as_str()
+                .is_some_and(|n| n.eq_ignore_ascii_case(category))
+        })
+        .and_then(|c| c["id"].as_str())
+        .ok_or_else(|| {
+            GripeError::NotFound(format!(
+                "Discussion category '{}' not found in {}",
+                category, repo
+            ))
+        })?;
+
+    let created = graphql(
+        "mutation($repo: ID!, $category: ID!, $title: String!, $body: String!) { createDiscussion(input: {repositoryId: $repo, categoryId: $category, title: $title, body: $body}) { discussion { url number } } }",
+        serde_json::json!({ "repo": repo_id, "category": category_id, "title": title, "body": body }),
+    )?;
+    let discussion = &created["data"]["createDiscussion"]["discussion"];
+    let url = discussion["url"]
+        .as_str()
+        .ok_or_else(|| GripeError::Other(format!("Failed to create discussion: {}", created)))?
+        .to_string();
+    let number = discussion["number"].as_u64().unwrap_or(0);
+
+    Ok(IssueResult { url, number })
+}
+
+/// Color for created labels that have no definition (GitHub's default grey).
+const DEFAULT_LABEL_COLOR: &str = "ededed";
+
+/// Names of all labels defined on `repo`.
+pub fn list_labels(repo: &str) -> Result<Vec<String>, GripeError> {
+    if let Backend::Api(api) = backend()? {
+        return api.list_labels(repo);
+    }
+
+    let output = Command::new("gh")
+        .args([
+            "label", "list", "--repo", repo, "--json", "name", "--limit", "1000",
+        ])
+        .output()
+        .map_err(spawn_error)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(gh_failure("gh label list", &stderr));
+    }
+
+    let labels: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| GripeError::Other(format!("Failed to parse gh output: {}", e)))?;
+    Ok(label_names(&labels))
+}
+
+/// Create a label on `repo`, using `definition` for its color and description.
+pub fn create_label(
+    repo: &str,
+    name: &str,
+    definition: Option<&LabelDefinition>,
+) -> Result<(), GripeError> {
+    let color = definition
+        .and_then(|d| d.color.as_deref())
+        .map(|c| c.trim_start_matches('#'))
+        .unwrap_or(DEFAULT_LABEL_COLOR);
+    let description = definition
+        .and_then(|d| d.description.as_deref())
+        .unwrap_or("");
+
+    if let Backend::Api(api) = backend()? {
+        return api.create_label(repo, name, color, description);
+    }
+
+    let output = Command::new("gh")
+        .args([
+            "label",
+            "create",
+            name,
+            "--repo",
+            repo,
+            "--color",
+            color,
+            "--description",
+            description,
+        ])
+        .output()
+        .map_err(spawn_error)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(gh_failure("gh label create", &stderr));
+    }
+
+    Ok(())
+}
+
+/// Create an issue. If some labels don't exist on the repo, they are created
+/// (with `MissingLabels::Create`) or left off, and the issue is filed with the rest.
 pub fn create_issue(
     repo: &str,
     title: &str,
     body: &str,
+    meta: &IssueMeta,
+    missing: MissingLabels,
+    definitions: &BTreeMap<String, LabelDefinition>,
+) -> Result<IssueResult, GripeError> {
+    let labels = &meta.labels;
+    if matches!(backend()?, Backend::Api(_)) && !labels.is_empty() {
+        // The REST API creates unknown labels itself (or drops them without
+        // push access), so check them before filing rather than after
+        let meta = IssueMeta {
+            labels: existing_labels(repo, labels, missing, definitions),
+            ..meta.clone()
+        };
+        let result = try_create_issue(repo, title, body, &meta)?
+            .map_err(|e| gh_failure("gh issue create", &e))?;
+        apply_post_create(repo, &result, &meta);
+        return Ok(result);
+    }
+
+    let stderr = match try_create_issue(repo, title, body, meta)? {
+        Ok(r) => {
+            apply_post_create(repo, &r, meta);
+            return Ok(r);
+        }
+        Err(stderr) if stderr.contains("label") && stderr.contains("not found") => stderr,
+        Err(stderr) => return Err(gh_failure("gh issue create", &stderr)),
+    };
+
+    let kept = existing_labels(repo, labels, missing, definitions);
+    if kept.len() == labels.len() {
+        // Every label exists now, so the failure wasn't about ours
+        return Err(gh_failure("gh issue create", &stderr));
+    }
+
+    let meta = IssueMeta {
+        labels: kept,
+        ..meta.clone()
+    };
+    let result = try_create_issue(repo, title, body, &meta)?
+        .map_err(|e| gh_failure("gh issue create", &e))?;
+    apply_post_create(repo, &result, &meta);
+    Ok(result)
+}
+
+/// The `labels` that exist on `repo` once `missing` has been applied.
+fn existing_labels(
+    repo: &str,
     labels: &[String],
-) -> Result<IssueResult, String> {
-    // Try with labels first, retry without if labels don't exist on the repo
-    let result = try_create_issue(repo, title, body, labels)?;
-    match result {
-        Ok(r) => Ok(r),
-        Err(stderr) if stderr.contains("label") && stderr.contains("not found") => {
+    missing: MissingLabels,
+    definitions: &BTreeMap<String, LabelDefinition>,
+) -> Vec<String> {
+    match list_labels(repo) {
+        Ok(existing) => resolve_missing_labels(repo, labels, &existing, missing, definitions),
+        Err(e) => {
+            eprintln!(
+                "{} Could not list labels on {} ({}); creating issue without labels",
+                "warning:".yellow(),
+                repo,
+                e
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Set the parts of `meta` that `gh issue create` can't: the issue type and
+/// project membership. The issue already exists, so failures only warn.
+fn apply_post_create(repo: &str, issue: &IssueResult, meta: &IssueMeta) {
+    if let Some(issue_type) = &meta.issue_type {
+        if let Err(e) = set_issue_type(repo, issue.number, issue_type) {
             eprintln!(
-                "{} Labels {:?} not found on repo, creating issue without labels",
+                "{} Could not set issue type '{}': {}",
                 "warning:".yellow(),
-                labels
+                issue_type,
+                e
             );
-            try_create_issue(repo, title, body, &[])?
-                .map_err(|e| format!("gh issue create failed: {}", e))
         }
-        Err(stderr) => Err(format!("gh issue create failed: {}", stderr)),
     }
+    for project in &meta.projects {
+        if let Err(e) = add_to_project(repo, issue.number, project) {
+            eprintln!(
+                "{} Could not add the issue to project {}: {}",
+                "warning:".yellow(),
+                project,
+                e
+            );
+        }
+    }
+}
+
+fn set_issue_type(repo: &str, number: u64, issue_type: &str) -> Result<(), GripeError> {
+    if let Backend::Api(api) = backend()? {
+        return api.set_issue_type(repo, number, issue_type);
+    }
+
+    let output = Command::new("gh")
+        .args([
+            "api",
+            "--method",
+            "PATCH",
+            &format!("repos/{}/issues/{}", repo, number),
+            "-f",
+            &format!("type={}", issue_type),
+        ])
+        .output()
+        .map_err(spawn_error)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(gh_failure("gh api", &stderr));
+    }
+    Ok(())
+}
+
+/// Add issue `number` to a project given as `owner/number`.
+fn add_to_project(repo: &str, number: u64, project: &str) -> Result<(), GripeError> {
+    let (owner, project_number) = project
+        .split_once('/')
+        .and_then(|(o, n)| Some((o, n.parse::<u64>().ok()?)))
+        .ok_or_else(|| {
+            GripeError::Validation(format!(
+                "Invalid project '{}'. Expected owner/number.",
+                project
+            ))
+        })?;
+    let (repo_owner, repo_name) = repo.split_once('/').unwrap_or((repo, ""));
+
+    let lookup = graphql(
+        "query($owner: String!, $number: Int!, $repoOwner: String!, $repoName: String!, $issue: Int!) { repositoryOwner(login: $owner) { ... on ProjectV2Owner { projectV2(number: $number) { id } } } repository(owner: $repoOwner, name: $repoName) { issue(number: $issue) { id } } }",
+        serde_json::json!({
+            "owner": owner,
+            "number": project_number,
+            "repoOwner": repo_owner,
+            "repoName": repo_name,
+            "issue": number,
+        }),
+    )?;
+    let project_id = lookup["data"]["repositoryOwner"]["projectV2"]["id"]
+        .as_str()
+        .ok_or_else(|| GripeError::NotFound(format!("Project {} not found", project)))?;
+    let issue_id = lookup["data"]["repository"]["issue"]["id"]
+        .as_str()
+        .ok_or_else(|| GripeError::NotFound(format!("Issue {}#{} not found", repo, number)))?;
+
+    graphql(
+        "mutation($project: ID!, $content: ID!) { addProjectV2ItemById(input: {projectId: $project, contentId: $content}) { item { id } } }",
+        serde_json::json!({ "project": project_id, "content": issue_id }),
+    )?;
+    Ok(())
+}
+
+/// Work out which of `labels` to file with: those that exist, plus any
+/// missing ones that could be created. Warns about the rest.
+fn resolve_missing_labels(
+    repo: &str,
+    labels: &[String],
+    existing: &[String],
+    missing: MissingLabels,
+    definitions: &BTreeMap<String, LabelDefinition>,
+) -> Vec<String> {
+    let mut kept = Vec::new();
+    let mut dropped = Vec::new();
+
+    for label in labels {
+        if existing.iter().any(|e| e.eq_ignore_ascii_case(label)) {
+            kept.push(label.clone());
+            continue;
+        }
+        if missing == MissingLabels::Create {
+            match create_label(repo, label, definitions.get(label)) {
+                Ok(()) => {
+                    kept.push(label.clone());
+                    continue;
+                }
+                Err(e) => eprintln!(
+                    "{} Could not create label '{}' on {}: {}",
+                    "warning:".yellow(),
+                    label,
+                    repo,
+                    e
+                ),
+            }
+        }
+        dropped.push(label.as_str());
+    }
+
+    if !dropped.is_empty() {
+        eprintln!(
+            "{} Labels not found on {}, creating issue without them: {}",
+            "warning:".yellow(),
+            repo,
+            dropped.join(", ")
+        );
+    }
+
+    kept
 }
 
 fn try_create_issue(
     repo: &str,
     title: &str,
     body: &str,
-    labels: &[String],
-) -> Result<Result<IssueResult, String>, String> {
+    meta: &IssueMeta,
+) -> Result<Result<IssueResult, String>, GripeError> {
+    if let Backend::Api(api) = backend()? {
+        return api.create_issue(repo, title, body, meta);
+    }
+
     let mut args = vec![
         "issue".to_string(),
         "create".to_string(),
@@ -132,19 +1119,25 @@ fn try_create_issue(
         repo.to_string(),
         "--title".to_string(),
         title.to_string(),
-        "--body".to_string(),
-        body.to_string(),
+        // Pass the body on stdin; large bodies can exceed argv limits
+        "--body-file".to_string(),
+        "-".to_string(),
     ];
 
-    for label in labels {
+    for label in &meta.labels {
         args.push("--label".to_string());
         args.push(label.to_string());
     }
+    for assignee in &meta.assignees {
+        args.push("--assignee".to_string());
+        args.push(assignee.to_string());
+    }
+    if let Some(milestone) = &meta.milestone {
+        args.push("--milestone".to_string());
+        args.push(milestone.to_string());
+    }
 
-    let output = Command::new("gh")
-        .args(&args)
-        .output()
-        .map_err(|e| format!("Failed to run gh: {}", e))?;
+    let output = run_gh_with_input(&args, body)?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
@@ -160,3 +1153,144 @@ fn try_create_issue(
 
     Ok(Ok(IssueResult { url, number }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_remote_policy_is_an_error() {
+        let policy = parse_remote_policy("o/r", "automated: deny\nfields: []\n").unwrap();
+        assert_eq!(policy.mode, crate::types::AutomatedMode::Deny);
+        assert!(parse_remote_policy("o/r", "fields: []\n").is_ok());
+
+        for bad in [
+            "automated: dney\n",
+            "automated: { mod: deny }\n",
+            "automated: [\n",
+        ] {
+            let err = parse_remote_policy("o/r", bad).unwrap_err();
+            assert!(matches!(err, GripeError::ConfigParse(_)), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_contents_errors_are_classified() {
+        let response = |status, body: &str| RawResponse {
+            status,
+            etag: None,
+            body: body.to_string(),
+        };
+        let err = |r: RawResponse| contents_error("o/r", "gripe.yaml", &r);
+        assert_eq!(
+            err(response(401, r#"{"message":"Bad credentials"}"#)).kind(),
+            "auth"
+        );
+        assert_eq!(
+            err(response(403, r#"{"message":"API rate limit exceeded"}"#)).kind(),
+            "network"
+        );
+        assert_eq!(err(response(502, "")).kind(), "network");
+    }
+
+    #[test]
+    fn test_parse_include_output() {
+        let raw = "HTTP/2.0 200 OK\r\nContent-Type: application/json\r\nEtag: W/\"abc123\"\r\n\r\n{\"content\":\"YXV0b21hdGVkOiBkZW55\"}";
+        let response = parse_include_output(raw);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.etag.as_deref(), Some("W/\"abc123\""));
+        assert!(response.body.starts_with("{\"content\""));
+    }
+
+    #[test]
+    fn test_parse_not_modified() {
+        let response = parse_include_output("HTTP/2.0 304 Not Modified\nEtag: \"x\"\n\n");
+        assert_eq!(response.status, 304);
+        assert_eq!(response.body, "");
+    }
+
+    #[test]
+    fn test_gh_failure_kinds() {
+        let kind = |stderr| gh_failure("gh issue create", stderr).kind();
+        assert_eq!(
+            kind("HTTP 404: Not Found (https://api.github.com/repos/o/r)"),
+            "not_found"
+        );
+        assert_eq!(
+            kind("To get started with GitHub CLI, please run:  gh auth login"),
+            "auth"
+        );
+        assert_eq!(kind("error connecting to api.github.com"), "network");
+        assert_eq!(kind("something odd"), "error");
+    }
+
+    #[test]
+    fn test_drop_only_missing_labels() {
+        let labels = vec![
+            "bug".to_string(),
+            "gripe".to_string(),
+            "Feedback".to_string(),
+        ];
+        let existing = vec!["bug".to_string(), "feedback".to_string()];
+        let kept = resolve_missing_labels(
+            "o/r",
+            &labels,
+            &existing,
+            MissingLabels::Drop,
+            &BTreeMap::new(),
+        );
+        assert_eq!(kept, vec!["bug", "Feedback"]);
+    }
+
+    #[test]
+    fn test_parse_issue_ref() {
+        let parse = |r| parse_issue_ref(r, Some("o/r"));
+        assert_eq!(parse("42"), Ok(("o/r".to_string(), 42)));
+        assert_eq!(parse("#7"), Ok(("o/r".to_string(), 7)));
+        assert_eq!(parse("acme/app#9"), Ok(("acme/app".to_string(), 9)));
+        assert_eq!(
+            parse("https://github.com/acme/app/issues/12#issuecomment-1"),
+            Ok(("acme/app".to_string(), 12))
+        );
+        assert!(parse("https://github.com/acme/app").is_err());
+        assert_eq!(
+            parse_issue_ref("42", None).unwrap_err().kind(),
+            "validation"
+        );
+    }
+
+    #[test]
+    fn test_parse_issue_status() {
+        let issue = serde_json::json!({
+            "number": 42,
+            "title": "Crash on save",
+            "url": "https://github.com/o/r/issues/42",
+            "state": "CLOSED",
+            "stateReason": "COMPLETED",
+            "labels": { "nodes": [{ "name": "bug" }] },
+            "assignees": { "nodes": [{ "login": "octocat" }] },
+            "closedByPullRequestsReferences": { "nodes": [
+                { "number": 50, "title": "Fix save", "url": "u", "state": "MERGED" }
+            ] },
+            "comments": { "nodes": [
+                { "author": null, "createdAt": "2026-01-01T00:00:00Z", "body": "Fixed" }
+            ] },
+        });
+        let status = parse_issue_status("o/r", &issue).unwrap();
+        assert_eq!(status.state, "closed");
+        assert_eq!(status.state_reason.as_deref(), Some("completed"));
+        assert_eq!(status.labels, vec!["bug"]);
+        assert_eq!(status.assignees, vec!["octocat"]);
+        assert_eq!(status.linked_prs[0].state, "merged");
+        assert_eq!(status.comments[0].author, "ghost");
+        assert!(parse_issue_status("o/r", &serde_json::Value::Null).is_none());
+    }
+
+    #[test]
+    fn test_base64_decode() {
+        assert_eq!(
+            base64_decode("YXV0b21hdGVkOiBkZW55").unwrap(),
+            "automated: deny"
+        );
+    }
+}
@@ -0,0 +1,509 @@
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::error::GripeError;
+use crate::gh::{label_names, IssueMeta, IssueResult, IssueSummary};
+
+const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// A raw HTTP response, as returned by the REST API or `gh api --include`.
+#[derive(Debug, Clone)]
+pub struct RawResponse {
+    pub status: u16,
+    pub etag: Option<String>,
+    pub body: String,
+}
+
+/// Talks to the GitHub REST and GraphQL APIs directly, for environments
+/// without the `gh` CLI.
+pub struct ApiClient {
+    agent: ureq::Agent,
+    base_url: String,
+    graphql_url: String,
+    token: String,
+}
+
+/// The API base URL: `GRIPE_API_URL`, then `GITHUB_API_URL` (set by GitHub
+/// Actions, including on GHES), then api.github.com.
+pub fn api_base_url() -> String {
+    ["GRIPE_API_URL", "GITHUB_API_URL"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_else(|| DEFAULT_API_URL.to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// The GraphQL endpoint for a REST base URL: `GRIPE_GRAPHQL_URL` if set,
+/// otherwise `{base}/graphql`, except on GHES where the REST API lives at
+/// `/api/v3` and GraphQL at `/api/graphql`.
+pub fn graphql_url(base_url: &str) -> String {
+    if let Some(url) = std::env::var("GRIPE_GRAPHQL_URL")
+        .ok()
+        .filter(|v| !v.is_empty())
+    {
+        return url;
+    }
+    let base = base_url.trim_end_matches('/');
+    match base.strip_suffix("/v3") {
+        Some(api) => format!("{}/graphql", api),
+        None => format!("{}/graphql", base),
+    }
+}
+
+/// A token from `GH_TOKEN` or `GITHUB_TOKEN`, the same variables gh reads.
+pub fn env_token() -> Option<String> {
+    ["GH_TOKEN", "GITHUB_TOKEN"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str, token: &str) -> Self {
+        ApiClient {
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(30))
+                .build(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            graphql_url: graphql_url(base_url),
+            token: token.to_string(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        self.request_url(method, &format!("{}/{}", self.base_url, path))
+    }
+
+    fn request_url(&self, method: &str, url: &str) -> ureq::Request {
+        self.agent
+            .request(method, url)
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .set("Accept", "application/vnd.github+json")
+            .set("X-GitHub-Api-Version", "2022-11-28")
+            .set("User-Agent", concat!("gripe/", env!("CARGO_PKG_VERSION")))
+    }
+
+    fn send(&self, request: ureq::Request, body: Option<Value>) -> Result<RawResponse, GripeError> {
+        let result = match body {
+            Some(body) => request.send_json(body),
+            None => request.call(),
+        };
+        let response = match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => {
+                return Err(GripeError::Network(format!(
+                    "Request to GitHub failed: {}",
+                    e
+                )))
+            }
+        };
+
+        let status = response.status();
+        let etag = response.header("etag").map(|e| e.to_string());
+        let body = response
+            .into_string()
+            .map_err(|e| GripeError::Network(format!("Failed to read GitHub response: {}", e)))?;
+
+        Ok(RawResponse { status, etag, body })
+    }
+
+    /// Send a request and parse a 2xx JSON response, or describe the failure.
+    fn send_expect(
+        &self,
+        request: ureq::Request,
+        body: Option<Value>,
+        action: &str,
+    ) -> Result<Value, GripeError> {
+        let response = self.send(request, body)?;
+        if !(200..300).contains(&response.status) {
+            return Err(GripeError::from_http(
+                response.status,
+                format!("{} failed: {}", action, describe_error(&response)),
+            ));
+        }
+        parse_json(&response.body)
+    }
+
+    /// GET `repos/{repo}/contents/{path}`, revalidating with `etag` if given.
+    pub fn get_contents(
+        &self,
+        repo: &str,
+        path: &str,
+        etag: Option<&str>,
+    ) -> Result<RawResponse, GripeError> {
+        let mut request = self.request("GET", &format!("repos/{}/contents/{}", repo, path));
+        if let Some(etag) = etag {
+            request = request.set("If-None-Match", etag);
+        }
+        self.send(request, None)
+    }
+
+    /// Create an issue. The inner error carries GitHub's message so callers
+    /// can retry, matching the gh backend.
+    pub fn create_issue(
+        &self,
+        repo: &str,
+        title: &str,
+        body: &str,
+        meta: &IssueMeta,
+    ) -> Result<Result<IssueResult, String>, GripeError> {
+        let mut assignees = meta.assignees.clone();
+        if let Some(me) = assignees.iter_mut().find(|a| *a == "@me") {
+            // gh resolves @me itself; the REST API needs the login
+            let user = self.send_expect(self.request("GET", "user"), None, "Looking up user")?;
+            *me = user["login"].as_str().unwrap_or_default().to_string();
+        }
+
+        let mut payload = json!({
+            "title": title,
+            "body": body,
+            "labels": meta.labels,
+            "assignees": assignees,
+        });
+        // The REST API takes a milestone number; gh takes its title
+        if let Some(milestone) = &meta.milestone {
+            match self.milestone_number(repo, milestone)? {
+                Some(number) => payload["milestone"] = json!(number),
+                None => return Ok(Err(format!("milestone '{}' not found", milestone))),
+            }
+        }
+
+        let request = self.request("POST", &format!("repos/{}/issues", repo));
+        let response = self.send(request, Some(payload))?;
+        if response.status != 201 {
+            return Ok(Err(describe_error(&response)));
+        }
+
+        let json = parse_json(&response.body)?;
+        Ok(Ok(IssueResult {
+            url: json["html_url"].as_str().unwrap_or_default().to_string(),
+            number: json["number"].as_u64().unwrap_or(0),
+        }))
+    }
+
+    /// Number of the open milestone titled `title`.
+    fn milestone_number(&self, repo: &str, title: &str) -> Result<Option<u64>, GripeError> {
+        let request = self
+            .request("GET", &format!("repos/{}/milestones", repo))
+            .query("state", "open")
+            .query("per_page", "100");
+        let json = self.send_expect(request, None, "Listing milestones")?;
+        Ok(json
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|m| m["title"].as_str() == Some(title))
+            .and_then(|m| m["number"].as_u64()))
+    }
+
+    pub fn set_issue_type(
+        &self,
+        repo: &str,
+        number: u64,
+        issue_type: &str,
+    ) -> Result<(), GripeError> {
+        let request = self.request("PATCH", &format!("repos/{}/issues/{}", repo, number));
+        self.send_expect(
+            request,
+            Some(json!({ "type": issue_type })),
+            "Setting issue type",
+        )?;
+        Ok(())
+    }
+
+    /// Comment on an issue, returning the comment's URL.
+    pub fn add_comment(&self, repo: &str, number: u64, body: &str) -> Result<String, GripeError> {
+        let request = self.request(
+            "POST",
+            &format!("repos/{}/issues/{}/comments", repo, number),
+        );
+        let comment = self.send_expect(request, Some(json!({ "body": body })), "Adding comment")?;
+        Ok(comment["html_url"].as_str().unwrap_or_default().to_string())
+    }
+
+    pub fn create_gist(
+        &self,
+        filename: &str,
+        content: &str,
+        description: &str,
+    ) -> Result<String, GripeError> {
+        let request = self.request("POST", "gists");
+        let json = self.send_expect(
+            request,
+            Some(json!({
+                "description": description,
+                "public": false,
+                "files": { filename: { "content": content } },
+            })),
+            "Creating gist",
+        )?;
+        Ok(json["html_url"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Names of all labels defined on `repo`.
+    pub fn list_labels(&self, repo: &str) -> Result<Vec<String>, GripeError> {
+        let mut names = Vec::new();
+        for page in 1.. {
+            let request = self
+                .request("GET", &format!("repos/{}/labels", repo))
+                .query("per_page", "100")
+                .query("page", &page.to_string());
+            let json = self.send_expect(request, None, "Listing labels")?;
+            let page_names = label_names(&json);
+            let full = page_names.len() == 100;
+            names.extend(page_names);
+            if !full {
+                break;
+            }
+        }
+        Ok(names)
+    }
+
+    pub fn create_label(
+        &self,
+        repo: &str,
+        name: &str,
+        color: &str,
+        description: &str,
+    ) -> Result<(), GripeError> {
+        let request = self.request("POST", &format!("repos/{}/labels", repo));
+        self.send_expect(
+            request,
+            Some(json!({ "name": name, "color": color, "description": description })),
+            "Creating label",
+        )?;
+        Ok(())
+    }
+
+    /// Count issues matching a search query.
+    pub fn search_issue_count(&self, query: &str) -> Result<usize, GripeError> {
+        let request = self
+            .request("GET", "search/issues")
+            .query("q", query)
+            .query("per_page", "1");
+        let json = self.send_expect(request, None, "Searching issues")?;
+        Ok(json["total_count"].as_u64().unwrap_or(0) as usize)
+    }
+
+    /// Up to `limit` issues matching a search query, newest first.
+    pub fn search_issues(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<IssueSummary>, GripeError> {
+        let mut issues = Vec::new();
+        for page in 1.. {
+            let request = self
+                .request("GET", "search/issues")
+                .query("q", query)
+                .query("sort", "created")
+                .query("order", "desc")
+                .query("per_page", "100")
+                .query("page", &page.to_string());
+            let json = self.send_expect(request, None, "Searching issues")?;
+            let items = json["items"].as_array().cloned().unwrap_or_default();
+            issues.extend(items.iter().map(|item| {
+                IssueSummary {
+                    // repository_url is https://api.github.com/repos/{owner}/{repo}
+                    repo: item["repository_url"]
+                        .as_str()
+                        .and_then(|u| u.split("/repos/").nth(1))
+                        .unwrap_or_default()
+                        .to_string(),
+                    number: item["number"].as_u64().unwrap_or(0),
+                    title: item["title"].as_str().unwrap_or_default().to_string(),
+                    url: item["html_url"].as_str().unwrap_or_default().to_string(),
+                    state: item["state"].as_str().unwrap_or_default().to_string(),
+                    labels: label_names(&item["labels"]),
+                    body: item["body"].as_str().unwrap_or_default().to_string(),
+                    created_at: item["created_at"].as_str().unwrap_or_default().to_string(),
+                }
+            }));
+            if items.len() < 100 || issues.len() >= limit {
+                break;
+            }
+        }
+        issues.truncate(limit);
+        Ok(issues)
+    }
+
+    pub fn graphql(&self, query: &str, variables: Value) -> Result<Value, GripeError> {
+        let request = self.request_url("POST", &self.graphql_url);
+        let json = self.send_expect(
+            request,
+            Some(json!({ "query": query, "variables": variables })),
+            "GraphQL request",
+        )?;
+        if let Some(errors) = json.get("errors") {
+            return Err(graphql_error(errors));
+        }
+        Ok(json)
+    }
+}
+
+fn parse_json(body: &str) -> Result<Value, GripeError> {
+    serde_json::from_str(body)
+        .map_err(|e| GripeError::Other(format!("Failed to parse GitHub response: {}", e)))
+}
+
+/// Classify the `errors` array of a GraphQL response by its first error type.
+pub fn graphql_error(errors: &Value) -> GripeError {
+    let message = format!("GraphQL request failed: {}", errors);
+    match errors[0]["type"].as_str() {
+        Some("NOT_FOUND") => GripeError::NotFound(message),
+        Some("FORBIDDEN") => GripeError::Auth(message),
+        Some("RATE_LIMITED") => GripeError::Network(message),
+        _ => GripeError::Other(message),
+    }
+}
+
+/// Turn an error response into a readable message.
+pub fn describe_error(response: &RawResponse) -> String {
+    let message = serde_json::from_str::<Value>(&response.body)
+        .ok()
+        .and_then(|json| json["message"].as_str().map(|m| m.to_string()))
+        .unwrap_or_else(|| response.body.trim().to_string());
+    format!("HTTP {}: {}", response.status, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    /// Serve one canned response on a local port, returning the base URL and
+    /// a handle that yields the raw request that was received.
+    fn serve_once(status: &str, headers: &str, body: &str) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+            status,
+            body.len(),
+            headers,
+            body
+        );
+
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = len.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str(&String::from_utf8_lossy(&body));
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            request
+        });
+
+        (base, handle)
+    }
+
+    #[test]
+    fn test_create_issue() {
+        let (base, server) = serve_once(
+            "201 Created",
+            "Content-Type: application/json\r\n",
+            r#"{"html_url":"https://github.com/o/r/issues/7","number":7}"#,
+        );
+        let client = ApiClient::new(&base, "secret");
+
+        let meta = IssueMeta {
+            labels: vec!["bug".to_string()],
+            assignees: vec!["octocat".to_string()],
+            ..Default::default()
+        };
+        let result = client
+            .create_issue("o/r", "Title", "Body", &meta)
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.number, 7);
+        assert_eq!(result.url, "https://github.com/o/r/issues/7");
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /repos/o/r/issues HTTP/1.1"));
+        assert!(request.contains("Authorization: Bearer secret"));
+        assert!(request.contains(r#""labels":["bug"]"#));
+        assert!(request.contains(r#""assignees":["octocat"]"#));
+    }
+
+    #[test]
+    fn test_get_contents_sends_etag() {
+        let (base, server) = serve_once("304 Not Modified", "ETag: \"abc\"\r\n", "");
+        let client = ApiClient::new(&base, "t");
+
+        let response = client
+            .get_contents("o/r", "gripe.yaml", Some("\"abc\""))
+            .unwrap();
+        assert_eq!(response.status, 304);
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("GET /repos/o/r/contents/gripe.yaml"));
+        assert!(request.contains("If-None-Match: \"abc\""));
+    }
+
+    #[test]
+    fn test_graphql_url() {
+        assert_eq!(
+            graphql_url("https://api.github.com"),
+            "https://api.github.com/graphql"
+        );
+        assert_eq!(
+            graphql_url("https://ghe.example.com/api/v3/"),
+            "https://ghe.example.com/api/graphql"
+        );
+    }
+
+    #[test]
+    fn test_search_issues() {
+        let (base, server) = serve_once(
+            "200 OK",
+            "Content-Type: application/json\r\n",
+            r#"{"items":[{"repository_url":"https://api.github.com/repos/o/r","number":3,"title":"t","html_url":"u","state":"open","labels":[{"name":"bug"}],"body":"b","created_at":"2026-01-02T00:00:00Z"}]}"#,
+        );
+        let client = ApiClient::new(&base, "t");
+
+        let issues = client.search_issues("is:issue author:@me", 30).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].repo, "o/r");
+        assert_eq!(issues[0].labels, vec!["bug"]);
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("GET /search/issues?q=is%3Aissue"));
+    }
+
+    #[test]
+    fn test_error_message_is_surfaced() {
+        let (base, server) = serve_once(
+            "404 Not Found",
+            "Content-Type: application/json\r\n",
+            r#"{"message":"Not Found"}"#,
+        );
+        let client = ApiClient::new(&base, "t");
+
+        let err = client.add_comment("o/r", 1, "hi").unwrap_err();
+        assert_eq!(
+            err,
+            GripeError::NotFound("Adding comment failed: HTTP 404: Not Found".to_string())
+        );
+        server.join().unwrap();
+    }
+}
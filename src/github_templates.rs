@@ -1,43 +1,112 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::types::{AutomatedPolicy, FieldDefinition, FieldType, GripeSchema};
+use crate::types::{
+    AutomatedPolicy, ExtraFields, FieldDefinition, FieldType, GripeSchema, MissingLabels,
+    RedactConfig,
+};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct GitHubTemplate {
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     name: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    /// Prefilled issue title.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     labels: Option<Vec<String>>,
+    #[serde(
+        default,
+        deserialize_with = "string_or_list",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    assignees: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    projects: Vec<String>,
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    issue_type: Option<String>,
     #[serde(default)]
     body: Vec<GitHubTemplateField>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Issue forms accept `assignees` as a list or a comma-separated string.
+fn string_or_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrList {
+        String(String),
+        List(Vec<String>),
+    }
+
+    Ok(match StringOrList::deserialize(deserializer)? {
+        StringOrList::String(s) => s
+            .split(',')
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty())
+            .collect(),
+        StringOrList::List(list) => list,
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 struct GitHubTemplateField {
     #[serde(rename = "type")]
     field_type: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     id: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     attributes: Option<GitHubFieldAttributes>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     validations: Option<GitHubFieldValidations>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 struct GitHubFieldAttributes {
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     label: Option<String>,
-    #[serde(default)]
-    options: Option<Vec<String>>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    options: Option<Vec<GitHubFieldOption>>,
+    /// Whether a dropdown allows several choices.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    multiple: bool,
+    /// Index of a dropdown's preselected option.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     value: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Dropdown options are plain strings; checkbox options are tables.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+enum GitHubFieldOption {
+    Text(String),
+    Checkbox {
+        label: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        required: bool,
+    },
+}
+
+impl GitHubFieldOption {
+    fn label(&self) -> &str {
+        match self {
+            GitHubFieldOption::Text(label) | GitHubFieldOption::Checkbox { label, .. } => label,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 struct GitHubFieldValidations {
     #[serde(default)]
     required: Option<bool>,
@@ -45,37 +114,95 @@ struct GitHubFieldValidations {
 
 /// Load the first usable GitHub issue template from the directory.
 pub fn load_github_templates(templates_dir: &Path) -> Option<GripeSchema> {
-    let entries = fs::read_dir(templates_dir).ok()?;
+    let mut paths: Vec<_> = fs::read_dir(templates_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
 
-    for entry in entries.flatten() {
-        let path = entry.path();
-        let ext = path.extension().and_then(|e| e.to_str());
-        if ext != Some("yml") && ext != Some("yaml") {
+    for path in paths {
+        if !is_template_file(&path.to_string_lossy()) {
             continue;
         }
 
-        let contents = fs::read_to_string(&path).ok();
-        let contents = contents.as_deref()?;
-
-        if let Ok(template) = serde_yaml::from_str::<GitHubTemplate>(contents) {
-            let fields = convert_template_fields(&template.body);
-            if fields.is_empty() {
-                continue;
-            }
-
-            return Some(GripeSchema {
-                repo: None,
-                automated: AutomatedPolicy::Allow,
-                labels: template.labels.unwrap_or_default(),
-                title_template: template.name.map(|n| format!("[{}] {{summary}}", n)),
-                fields,
-            });
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+        if let Some(schema) = parse_github_template(&contents) {
+            return Some(schema);
         }
     }
 
     None
 }
 
+/// Whether a path looks like an issue form (`.yml`/`.yaml`, not `config.yml`).
+pub fn is_template_file(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    (name.ends_with(".yml") || name.ends_with(".yaml"))
+        && name != "config.yml"
+        && name != "config.yaml"
+}
+
+/// Convert one issue form into a schema, if it has any usable fields.
+pub fn parse_github_template(contents: &str) -> Option<GripeSchema> {
+    let template = serde_yaml::from_str::<GitHubTemplate>(contents).ok()?;
+    let fields = convert_template_fields(&template.body);
+    if fields.is_empty() {
+        return None;
+    }
+
+    Some(GripeSchema {
+        name: template.name.clone(),
+        repo: None,
+        automated: AutomatedPolicy::default(),
+        labels: template.labels.unwrap_or_default(),
+        labels_from: BTreeMap::new(),
+        missing_labels: MissingLabels::default(),
+        label_definitions: BTreeMap::new(),
+        assignees: template.assignees,
+        milestone: None,
+        projects: template.projects,
+        issue_type: template.issue_type,
+        routing: Vec::new(),
+        codeowners: None,
+        title_template: title_template(
+            template.title.as_deref(),
+            template.name.as_deref(),
+            &fields,
+        ),
+        body_template: None,
+        redact: RedactConfig::default(),
+        extra_fields: ExtraFields::default(),
+        fields,
+        comment_fields: Vec::new(),
+    })
+}
+
+/// A title template from the form's `title` prefill (or its name), followed
+/// by the field most likely to sum up the issue: the first required input,
+/// else the first input. Without one, the title is the prefill alone.
+fn title_template(
+    title: Option<&str>,
+    name: Option<&str>,
+    fields: &[FieldDefinition],
+) -> Option<String> {
+    let inputs = || fields.iter().filter(|f| f.field_type == FieldType::Input);
+    let subject = inputs()
+        .find(|f| f.required)
+        .or_else(|| inputs().next())
+        .map(|f| format!("{{{}}}", f.id));
+    let escape = |text: &str| text.replace('{', "\\{").replace('}', "\\}");
+
+    match (title, name, subject) {
+        (Some(title), _, Some(subject)) => Some(format!("{}{}", escape(title), subject)),
+        (Some(title), _, None) => Some(escape(title.trim_end())),
+        (None, Some(name), Some(subject)) => Some(format!("[{}] {}", escape(name), subject)),
+        (None, _, _) => None,
+    }
+}
+
 fn convert_template_fields(body: &[GitHubTemplateField]) -> Vec<FieldDefinition> {
     body.iter()
         .filter_map(|field| {
@@ -85,25 +212,287 @@ fn convert_template_fields(body: &[GitHubTemplateField]) -> Vec<FieldDefinition>
             }
 
             let attrs = field.attributes.as_ref();
+            let options: Vec<GitHubFieldOption> =
+                attrs.and_then(|a| a.options.clone()).unwrap_or_default();
+            let multiple = attrs.is_some_and(|a| a.multiple);
             let field_type = match field.field_type.as_str() {
                 "input" => FieldType::Input,
                 "textarea" => FieldType::Textarea,
+                "dropdown" if multiple => FieldType::List,
                 "dropdown" => FieldType::Select,
+                // A single checkbox is a yes/no question; several are a checklist
+                "checkboxes" if options.len() == 1 => FieldType::Checkbox,
+                "checkboxes" => FieldType::List,
                 _ => FieldType::Input,
             };
-
-            Some(FieldDefinition {
-                id: id.clone(),
-                label: attrs.and_then(|a| a.label.clone()),
-                field_type,
-                required: field
+            let required = match (&field_type, options.as_slice()) {
+                (FieldType::Checkbox, [GitHubFieldOption::Checkbox { required, .. }]) => *required,
+                _ => field
                     .validations
                     .as_ref()
                     .and_then(|v| v.required)
                     .unwrap_or(false),
-                options: attrs.and_then(|a| a.options.clone()).unwrap_or_default(),
-                default: attrs.and_then(|a| a.value.clone()),
+            };
+            let label = match (&field_type, options.as_slice()) {
+                (FieldType::Checkbox, [option]) => Some(option.label().to_string()),
+                _ => attrs.and_then(|a| a.label.clone()),
+            };
+            let default = match attrs.and_then(|a| a.default) {
+                Some(index) => options.get(index).map(|o| o.label().to_string()),
+                None => attrs.and_then(|a| a.value.clone()),
+            };
+
+            Some(FieldDefinition {
+                id: id.clone(),
+                label,
+                description: attrs.and_then(|a| a.description.clone()),
+                field_type,
+                required,
+                options: options.iter().map(|o| o.label().to_string()).collect(),
+                default,
+                pattern: None,
+                min: None,
+                max: None,
             })
         })
+        .map(|mut field| {
+            if field.field_type == FieldType::Checkbox {
+                field.options.clear();
+            }
+            field
+        })
         .collect()
 }
+
+/// Render a schema as a GitHub issue form. Constraints issue forms can't
+/// express (`pattern`, `min`, `max`) are left out.
+pub fn to_github_form(schema: &GripeSchema) -> Result<String, String> {
+    let name = schema
+        .name
+        .clone()
+        .unwrap_or_else(|| "Feedback".to_string());
+    let form = GitHubTemplate {
+        name: Some(name),
+        description: Some("Generated from gripe.yaml".to_string()),
+        title: schema.title_template.as_deref().and_then(title_prefix),
+        labels: Some(schema.labels.clone()).filter(|l| !l.is_empty()),
+        assignees: schema
+            .assignees
+            .iter()
+            .map(|a| a.trim_start_matches('@').to_string())
+            .collect(),
+        projects: schema.projects.clone(),
+        issue_type: schema.issue_type.clone(),
+        body: schema.fields.iter().map(form_field).collect(),
+    };
+
+    let yaml = serde_yaml::to_string(&form).map_err(|e| e.to_string())?;
+    Ok(format!(
+        "# Generated from gripe.yaml with `gripe convert --to github-form`.\n{}",
+        yaml
+    ))
+}
+
+fn form_field(field: &FieldDefinition) -> GitHubTemplateField {
+    let label = field.display_label().to_string();
+    let mut attributes = GitHubFieldAttributes {
+        label: Some(label.clone()),
+        description: field.description.clone(),
+        ..Default::default()
+    };
+    let mut required = field.required;
+
+    let field_type = match field.field_type {
+        FieldType::Input | FieldType::Number => {
+            attributes.value = field.default.clone();
+            "input"
+        }
+        FieldType::Textarea | FieldType::File | FieldType::List => {
+            attributes.value = field.default.clone();
+            if attributes.description.is_none() {
+                attributes.description = match field.field_type {
+                    FieldType::File => Some("Paste the file's contents".to_string()),
+                    FieldType::List => Some("One item per line".to_string()),
+                    _ => None,
+                };
+            }
+            "textarea"
+        }
+        FieldType::Select => {
+            attributes.options = Some(
+                field
+                    .options
+                    .iter()
+                    .map(|o| GitHubFieldOption::Text(o.clone()))
+                    .collect(),
+            );
+            attributes.default = field
+                .default
+                .as_ref()
+                .and_then(|d| field.options.iter().position(|o| o == d));
+            "dropdown"
+        }
+        FieldType::Checkbox => {
+            // Checkbox forms mark the option required, not the field
+            attributes.options = Some(vec![GitHubFieldOption::Checkbox {
+                label,
+                required: field.required,
+            }]);
+            required = false;
+            "checkboxes"
+        }
+    };
+
+    GitHubTemplateField {
+        field_type: field_type.to_string(),
+        id: Some(field.id.clone()),
+        attributes: Some(attributes),
+        validations: required.then_some(GitHubFieldValidations {
+            required: Some(true),
+        }),
+    }
+}
+
+/// The literal start of a title template, such as `[Bug] ` in
+/// `[Bug] {summary}`, which issue forms can prefill.
+fn title_prefix(template: &str) -> Option<String> {
+    let mut prefix = String::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // Escapes work as in `TitleTemplate`; a trailing `\` is literal
+            '\\' => prefix.push(chars.next().unwrap_or('\\')),
+            '{' => break,
+            c => prefix.push(c),
+        }
+    }
+    prefix
+        .chars()
+        .any(|c| c.is_alphanumeric())
+        .then_some(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORM: &str = "\
+name: Bug report
+description: File a bug
+title: \"[Bug]: \"
+labels: [bug]
+body:
+  - type: markdown
+    attributes:
+      value: Thanks!
+  - type: input
+    id: summary
+    attributes:
+      label: Summary
+    validations:
+      required: true
+  - type: dropdown
+    id: os
+    attributes:
+      label: OS
+      options: [linux, macos]
+      default: 1
+  - type: checkboxes
+    id: terms
+    attributes:
+      label: Code of conduct
+      options:
+        - label: I agree to follow the code of conduct
+          required: true
+";
+
+    #[test]
+    fn test_is_template_file() {
+        assert!(is_template_file(".github/ISSUE_TEMPLATE/bug.yml"));
+        assert!(is_template_file("feature.yaml"));
+        assert!(!is_template_file(".github/ISSUE_TEMPLATE/config.yml"));
+        assert!(!is_template_file("config.yaml"));
+        assert!(!is_template_file(".github/ISSUE_TEMPLATE/bug.md"));
+    }
+
+    #[test]
+    fn test_forms_without_inputs_are_skipped() {
+        assert!(parse_github_template(
+            "name: Docs\nbody:\n  - type: markdown\n    attributes: { value: hi }\n"
+        )
+        .is_none());
+        assert!(parse_github_template("blank_issues_enabled: false\n").is_none());
+        assert!(parse_github_template(FORM).is_some());
+    }
+
+    #[test]
+    fn test_parse_form() {
+        let schema = parse_github_template(FORM).unwrap();
+        assert_eq!(schema.title_template.as_deref(), Some("[Bug]: {summary}"));
+        assert_eq!(schema.fields.len(), 3);
+        assert_eq!(schema.fields[1].default.as_deref(), Some("macos"));
+        let terms = &schema.fields[2];
+        assert_eq!(terms.field_type, FieldType::Checkbox);
+        assert!(terms.required);
+        assert_eq!(
+            terms.display_label(),
+            "I agree to follow the code of conduct"
+        );
+    }
+
+    #[test]
+    fn test_title_uses_a_form_field() {
+        let form = "\
+name: Crash
+title: \"[Crash]: \"
+body:
+  - type: textarea
+    id: what
+    attributes: { label: What happened }
+  - type: input
+    id: version
+    attributes: { label: Version }
+  - type: input
+    id: headline
+    attributes: { label: Headline }
+    validations: { required: true }
+";
+        let schema = parse_github_template(form).unwrap();
+        assert_eq!(
+            schema.title_template.as_deref(),
+            Some("[Crash]: {headline}")
+        );
+        let yaml = crate::config::schema_to_yaml(&schema).unwrap();
+        assert_eq!(crate::lint::lint(&yaml), vec![]);
+
+        let no_inputs = "title: \"Crash report\"\nbody:\n  - { type: textarea, id: what }\n";
+        let schema = parse_github_template(no_inputs).unwrap();
+        assert_eq!(schema.title_template.as_deref(), Some("Crash report"));
+        let yaml = crate::config::schema_to_yaml(&schema).unwrap();
+        assert_eq!(crate::lint::lint(&yaml), vec![]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let schema = parse_github_template(FORM).unwrap();
+        let form = to_github_form(&schema).unwrap();
+        assert!(form.contains("title: '[Bug]: '"));
+        let again = parse_github_template(&form).unwrap();
+        assert_eq!(
+            serde_yaml::to_value(&again.fields).unwrap(),
+            serde_yaml::to_value(&schema.fields).unwrap()
+        );
+        assert_eq!(again.title_template, schema.title_template);
+    }
+
+    #[test]
+    fn test_title_prefix() {
+        assert_eq!(title_prefix("[Bug] {summary}").as_deref(), Some("[Bug] "));
+        assert_eq!(
+            title_prefix("\\{core\\} {summary}").as_deref(),
+            Some("{core} ")
+        );
+        assert_eq!(title_prefix("{summary}"), None);
+        assert_eq!(title_prefix(": {summary}"), None);
+    }
+}
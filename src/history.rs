@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::GripeError;
+
+/// One successful submission, as recorded in the history log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Entry {
+    pub timestamp: String,
+    pub repo: String,
+    pub number: u64,
+    pub url: String,
+    pub title: String,
+    /// `issue` or `discussion`.
+    pub kind: String,
+    /// Field values after redaction.
+    pub fields: HashMap<String, String>,
+    pub schema_source: String,
+}
+
+/// `history.jsonl` under the user data directory (e.g. `~/.local/share/gripe/`).
+fn history_path() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("gripe").join("history.jsonl"))
+}
+
+/// Append an entry to the history log.
+pub fn append(entry: &Entry) -> Result<(), GripeError> {
+    let path = history_path()
+        .ok_or_else(|| GripeError::Other("Could not determine the user data directory".into()))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| GripeError::Other(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+
+    let line = serde_json::to_string(entry)
+        .map_err(|e| GripeError::Other(format!("JSON error: {}", e)))?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line))
+        .map_err(|e| GripeError::Other(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+/// All recorded submissions, oldest first. Lines that don't parse are skipped.
+pub fn load() -> Result<Vec<Entry>, GripeError> {
+    let Some(path) = history_path() else {
+        return Ok(Vec::new());
+    };
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(parse(&contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(GripeError::Other(format!(
+            "Failed to read {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+fn parse(contents: &str) -> Vec<Entry> {
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_skips_bad_lines() {
+        let entry = Entry {
+            timestamp: "2026-01-02T03:04:05Z".to_string(),
+            repo: "o/r".to_string(),
+            number: 7,
+            url: "https://github.com/o/r/issues/7".to_string(),
+            title: "t".to_string(),
+            kind: "issue".to_string(),
+            fields: HashMap::from([("summary".to_string(), "s".to_string())]),
+            schema_source: "built-in default".to_string(),
+        };
+        let contents = format!(
+            "{}\nnot json\n\n{}\n",
+            serde_json::to_string(&entry).unwrap(),
+            serde_json::to_string(&entry).unwrap()
+        );
+        assert_eq!(parse(&contents), vec![entry.clone(), entry]);
+    }
+}
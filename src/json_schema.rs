@@ -0,0 +1,143 @@
+use serde_json::{json, Map, Value};
+
+use crate::types::{ExtraFields, FieldDefinition, FieldType, GripeSchema};
+use crate::values::parse_checkbox;
+
+/// Describe the values `gripe submit --json` accepts for `schema` as a JSON
+/// Schema (draft 2020-12), for validating payloads or declaring tool inputs.
+pub fn to_json_schema(schema: &GripeSchema) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+
+    for field in &schema.fields {
+        properties.insert(field.id.clone(), field_schema(field));
+        if field.required {
+            required.push(field.id.clone());
+        }
+    }
+    if schema.automated.require_reporter {
+        properties.insert(
+            "reporter".to_string(),
+            json!({
+                "type": "string",
+                "minLength": 1,
+                "description": "Who is submitting; required for automated submissions",
+            }),
+        );
+        required.push("reporter".to_string());
+    }
+
+    let title = schema.name.as_deref().unwrap_or("gripe submission");
+    let description = match &schema.repo {
+        Some(repo) => format!("Field values for an issue filed in {} with gripe", repo),
+        None => "Field values for an issue filed with gripe".to_string(),
+    };
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": title,
+        "description": description,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": schema.extra_fields != ExtraFields::Reject,
+    })
+}
+
+fn field_schema(field: &FieldDefinition) -> Value {
+    let mut property = match field.field_type {
+        FieldType::Number => {
+            let mut number = json!({ "type": "number" });
+            if let Some(min) = field.min {
+                number["minimum"] = json!(min);
+            }
+            if let Some(max) = field.max {
+                number["maximum"] = json!(max);
+            }
+            number
+        }
+        FieldType::Checkbox if field.required => json!({ "type": "boolean", "const": true }),
+        FieldType::Checkbox => json!({ "type": "boolean" }),
+        FieldType::List => {
+            let mut items = json!({ "type": "string" });
+            if let Some(pattern) = &field.pattern {
+                items["pattern"] = json!(pattern);
+            }
+            let mut list = json!({ "type": "array", "items": items });
+            if field.required {
+                list["minItems"] = json!(1);
+            }
+            list
+        }
+        FieldType::Input | FieldType::Textarea | FieldType::Select | FieldType::File => {
+            let mut text = json!({ "type": "string" });
+            if field.field_type == FieldType::Select && !field.options.is_empty() {
+                text["enum"] = json!(field.options);
+            }
+            if let Some(pattern) = &field.pattern {
+                text["pattern"] = json!(pattern);
+            }
+            if field.required {
+                text["minLength"] = json!(1);
+            }
+            text
+        }
+    };
+
+    property["title"] = json!(field.display_label());
+    let description = match (&field.description, &field.field_type) {
+        (Some(description), _) => Some(description.clone()),
+        (None, FieldType::File) => Some("Path to a text file to attach".to_string()),
+        (None, _) => None,
+    };
+    if let Some(description) = description {
+        property["description"] = json!(description);
+    }
+    if let Some(default) = &field.default {
+        property["default"] = match field.field_type {
+            FieldType::Number => default
+                .parse::<f64>()
+                .map(|n| json!(n))
+                .unwrap_or(json!(default)),
+            FieldType::Checkbox => json!(parse_checkbox(default).unwrap_or(false)),
+            FieldType::List => json!(default
+                .split(['\n', ','])
+                .map(|item| item.trim())
+                .filter(|item| !item.is_empty())
+                .collect::<Vec<_>>()),
+            _ => json!(default),
+        };
+    }
+
+    property
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_schemas() {
+        let schema: GripeSchema = serde_yaml::from_str(
+            "name: bug\nautomated: { mode: allow, require_reporter: true }\nfields:\n  - { id: summary, required: true, description: One line }\n  - { id: severity, type: select, options: [low, high], default: low }\n  - { id: count, type: number, min: 1 }\n  - { id: searched, type: checkbox, required: true }\n  - { id: tags, type: list, pattern: '^[a-z]+$' }\n",
+        )
+        .unwrap();
+        let out = to_json_schema(&schema);
+
+        assert_eq!(out["title"], "bug");
+        assert_eq!(out["required"], json!(["summary", "searched", "reporter"]));
+        assert_eq!(out["additionalProperties"], true);
+        let props = &out["properties"];
+        assert_eq!(props["summary"]["minLength"], 1);
+        assert_eq!(props["summary"]["description"], "One line");
+        assert_eq!(props["severity"]["enum"], json!(["low", "high"]));
+        assert_eq!(props["severity"]["default"], "low");
+        assert_eq!(props["count"]["minimum"], 1.0);
+        assert_eq!(props["searched"]["const"], true);
+        assert_eq!(props["tags"]["items"]["pattern"], "^[a-z]+$");
+
+        let strict: GripeSchema =
+            serde_yaml::from_str("extra_fields: reject\nfields: [{ id: summary }]").unwrap();
+        assert_eq!(to_json_schema(&strict)["additionalProperties"], false);
+    }
+}
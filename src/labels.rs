@@ -0,0 +1,96 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::types::LabelRule;
+
+/// GitHub rejects label names longer than this many characters.
+const MAX_LABEL_CHARS: usize = 50;
+
+/// Labels implied by field values under the schema's `labels_from` rules.
+/// Fields without a value, and values a table doesn't mention, add nothing.
+pub fn derive_labels(
+    rules: &BTreeMap<String, LabelRule>,
+    values: &HashMap<String, String>,
+) -> Vec<String> {
+    let mut labels = Vec::new();
+
+    for (field, rule) in rules {
+        let Some(value) = values
+            .get(field)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+        else {
+            continue;
+        };
+        let label = match rule {
+            LabelRule::Template(template) => Some(
+                template
+                    .replace("{value}", &clean(value))
+                    .chars()
+                    .take(MAX_LABEL_CHARS)
+                    .collect(),
+            ),
+            LabelRule::Map(map) => map
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(value))
+                .map(|(_, label)| label.clone()),
+        };
+        if let Some(label) = label.filter(|l| !l.is_empty()) {
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+    }
+
+    labels
+}
+
+/// Make a free-form value safe to use in a label name: single-line and
+/// without commas, which gh treats as separators in `--label`.
+fn clean(value: &str) -> String {
+    value
+        .replace(',', " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(yaml: &str) -> BTreeMap<String, LabelRule> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_map_and_template_rules() {
+        let rules = rules(
+            "severity:\n  critical: priority:p0\n  high: priority:p1\ntool: \"tool:{value}\"\n",
+        );
+        assert_eq!(
+            derive_labels(
+                &rules,
+                &values(&[("severity", "Critical"), ("tool", "rustfmt")])
+            ),
+            vec!["priority:p0", "tool:rustfmt"]
+        );
+        assert!(derive_labels(&rules, &values(&[("severity", "low")])).is_empty());
+    }
+
+    #[test]
+    fn test_values_are_cleaned() {
+        let rules = rules("tool: \"tool:{value}\"\n");
+        let long = "x".repeat(80);
+        let labels = derive_labels(&rules, &values(&[("tool", "a, b\nc")]));
+        assert_eq!(labels, vec!["tool:a b c"]);
+        let labels = derive_labels(&rules, &values(&[("tool", &long)]));
+        assert_eq!(labels[0].chars().count(), MAX_LABEL_CHARS);
+    }
+}
@@ -0,0 +1,634 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use regex::Regex;
+use serde::Serialize;
+use serde_yaml::Value;
+
+use crate::template::Template;
+use crate::title_template::TitleTemplate;
+use crate::types::{BodyTemplate, FieldDefinition, FieldType, GripeSchema};
+use crate::values::check_value;
+
+// Keys gripe.yaml understands at each level; anything else is a typo serde
+// would silently ignore
+const SCHEMA_KEYS: &[&str] = &[
+    "name",
+    "repo",
+    "automated",
+    "labels",
+    "labels_from",
+    "missing_labels",
+    "label_definitions",
+    "assignees",
+    "milestone",
+    "projects",
+    "type",
+    "routing",
+    "codeowners",
+    "title_template",
+    "body_template",
+    "redact",
+    "extra_fields",
+    "fields",
+    "comment_fields",
+];
+const FIELD_KEYS: &[&str] = &[
+    "id",
+    "label",
+    "description",
+    "type",
+    "required",
+    "options",
+    "default",
+    "pattern",
+    "min",
+    "max",
+];
+const ROUTING_KEYS: &[&str] = &[
+    "when",
+    "labels",
+    "assignees",
+    "milestone",
+    "projects",
+    "type",
+];
+const CODEOWNERS_KEYS: &[&str] = &["field", "path"];
+const LABEL_DEFINITION_KEYS: &[&str] = &["color", "description"];
+const REDACT_KEYS: &[&str] = &["enabled", "patterns"];
+const AUTOMATED_KEYS: &[&str] = &[
+    "mode",
+    "rate_limit",
+    "labels",
+    "allowed_agents",
+    "blocked_agents",
+    "require_reporter",
+    "review",
+];
+const REVIEW_KEYS: &[&str] = &["repo", "discussion_category"];
+const BODY_TEMPLATE_KEYS: &[&str] = &["file"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in a gripe.yaml, with a 1-based position.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// One step of a path into the YAML document.
+#[derive(Debug, Clone, PartialEq)]
+enum Seg {
+    Key(String),
+    Index(usize),
+}
+
+fn key(k: &str) -> Seg {
+    Seg::Key(k.to_string())
+}
+
+fn display_path(path: &[Seg]) -> String {
+    let mut out = String::new();
+    for seg in path {
+        match seg {
+            Seg::Key(k) if out.is_empty() => out.push_str(k),
+            Seg::Key(k) => {
+                out.push('.');
+                out.push_str(k);
+            }
+            Seg::Index(i) => out.push_str(&format!("[{}]", i)),
+        }
+    }
+    out
+}
+
+/// Diagnostics located by path, turned into positions at the end.
+struct Findings(Vec<(Vec<Seg>, Severity, String)>);
+
+impl Findings {
+    fn error(&mut self, path: Vec<Seg>, message: impl fmt::Display) {
+        self.0.push((path, Severity::Error, message.to_string()));
+    }
+
+    fn warning(&mut self, path: Vec<Seg>, message: impl fmt::Display) {
+        self.0.push((path, Severity::Warning, message.to_string()));
+    }
+}
+
+/// Check gripe.yaml contents for mistakes serde accepts or only reports
+/// one at a time.
+pub fn lint(source: &str) -> Vec<Diagnostic> {
+    let value: Value = match serde_yaml::from_str(source) {
+        Ok(value) => value,
+        Err(e) => return vec![yaml_error(&e)],
+    };
+
+    let mut findings = Findings(Vec::new());
+    unknown_keys(&value, &mut findings);
+
+    match serde_yaml::from_str::<GripeSchema>(source) {
+        Ok(schema) => check_schema(&schema, &mut findings),
+        Err(e) => {
+            let mut diagnostics = resolve(source, findings);
+            diagnostics.push(yaml_error(&e));
+            return diagnostics;
+        }
+    }
+
+    resolve(source, findings)
+}
+
+fn yaml_error(e: &serde_yaml::Error) -> Diagnostic {
+    let (line, column) = e
+        .location()
+        .map(|l| (l.line(), l.column()))
+        .unwrap_or((1, 1));
+    // serde_yaml appends the position to its messages; it's shown separately
+    let message = e.to_string();
+    let message = match message.rfind(" at line ") {
+        Some(i) => message[..i].to_string(),
+        None => message,
+    };
+    Diagnostic {
+        line,
+        column,
+        severity: Severity::Error,
+        message,
+    }
+}
+
+fn resolve(source: &str, findings: Findings) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = findings
+        .0
+        .into_iter()
+        .map(|(path, severity, message)| {
+            let (line, column) = locate(source, &path);
+            Diagnostic {
+                line,
+                column,
+                severity,
+                message,
+            }
+        })
+        .collect();
+    diagnostics.sort_by_key(|d| (d.line, d.column));
+    diagnostics
+}
+
+fn unknown_keys(root: &Value, findings: &mut Findings) {
+    check_keys(root, SCHEMA_KEYS, &[], findings);
+
+    for list in ["fields", "comment_fields"] {
+        for (i, item) in items(root.get(list)) {
+            check_keys(item, FIELD_KEYS, &[key(list), Seg::Index(i)], findings);
+        }
+    }
+    for (i, item) in items(root.get("routing")) {
+        check_keys(
+            item,
+            ROUTING_KEYS,
+            &[key("routing"), Seg::Index(i)],
+            findings,
+        );
+    }
+    if let Some(Value::Mapping(definitions)) = root.get("label_definitions") {
+        for (name, definition) in definitions {
+            if let Some(name) = name.as_str() {
+                let path = [key("label_definitions"), key(name)];
+                check_keys(definition, LABEL_DEFINITION_KEYS, &path, findings);
+            }
+        }
+    }
+    if let Some(codeowners) = root.get("codeowners") {
+        check_keys(codeowners, CODEOWNERS_KEYS, &[key("codeowners")], findings);
+    }
+    if let Some(redact) = root.get("redact") {
+        check_keys(redact, REDACT_KEYS, &[key("redact")], findings);
+    }
+    if let Some(body_template) = root.get("body_template") {
+        check_keys(
+            body_template,
+            BODY_TEMPLATE_KEYS,
+            &[key("body_template")],
+            findings,
+        );
+    }
+    if let Some(automated) = root.get("automated") {
+        check_keys(automated, AUTOMATED_KEYS, &[key("automated")], findings);
+        if let Some(review) = automated.get("review") {
+            let path = [key("automated"), key("review")];
+            check_keys(review, REVIEW_KEYS, &path, findings);
+        }
+    }
+}
+
+fn items(value: Option<&Value>) -> impl Iterator<Item = (usize, &Value)> {
+    value
+        .and_then(|v| v.as_sequence())
+        .into_iter()
+        .flatten()
+        .enumerate()
+}
+
+fn check_keys(value: &Value, known: &[&str], path: &[Seg], findings: &mut Findings) {
+    let Value::Mapping(map) = value else {
+        return;
+    };
+    for name in map.keys().filter_map(|k| k.as_str()) {
+        if known.contains(&name) {
+            continue;
+        }
+        let location = if path.is_empty() {
+            String::new()
+        } else {
+            format!(" in {}", display_path(path))
+        };
+        let hint = closest(name, known.iter().copied())
+            .map(|s| format!(" (did you mean `{}`?)", s))
+            .unwrap_or_default();
+        let mut key_path = path.to_vec();
+        key_path.push(key(name));
+        findings.error(
+            key_path,
+            format!("unknown key `{}`{}{}", name, location, hint),
+        );
+    }
+}
+
+fn check_schema(schema: &GripeSchema, findings: &mut Findings) {
+    check_fields(&schema.fields, "fields", findings);
+    check_fields(&schema.comment_fields, "comment_fields", findings);
+
+    let ids: HashSet<&str> = schema.fields.iter().map(|f| f.id.as_str()).collect();
+    let unknown_field = |name: &str| {
+        let hint = closest(name, ids.iter().copied())
+            .map(|s| format!(" (did you mean `{}`?)", s))
+            .unwrap_or_default();
+        format!("unknown field `{}`{}", name, hint)
+    };
+
+    if let Some(template) = &schema.title_template {
+        match TitleTemplate::parse(template) {
+            Ok(parsed) => {
+                for name in parsed.fields().into_iter().filter(|n| !ids.contains(n)) {
+                    findings.error(
+                        vec![key("title_template")],
+                        format!("title_template uses {}", unknown_field(name)),
+                    );
+                }
+            }
+            Err(e) => findings.error(
+                vec![key("title_template")],
+                format!("invalid title_template: {}", e),
+            ),
+        }
+    }
+    if let Some(BodyTemplate::Inline(template)) = &schema.body_template {
+        if let Err(e) = Template::parse(template) {
+            findings.error(
+                vec![key("body_template")],
+                format!("invalid body_template: {}", e),
+            );
+        }
+    }
+
+    for (i, pattern) in schema.redact.patterns.iter().enumerate() {
+        if let Err(e) = Regex::new(pattern) {
+            // The regex crate's message spans several lines
+            let reason = e.to_string();
+            let reason = reason.lines().last().unwrap_or_default().trim();
+            findings.error(
+                vec![key("redact"), key("patterns"), Seg::Index(i)],
+                format!("invalid regex `{}`: {}", pattern, reason),
+            );
+        }
+    }
+
+    // References to fields that don't exist never match
+    for name in schema
+        .labels_from
+        .keys()
+        .filter(|n| !ids.contains(n.as_str()))
+    {
+        findings.warning(
+            vec![key("labels_from"), key(name)],
+            format!("labels_from uses {}", unknown_field(name)),
+        );
+    }
+    for (i, rule) in schema.routing.iter().enumerate() {
+        for name in rule.when.keys().filter(|n| !ids.contains(n.as_str())) {
+            findings.warning(
+                vec![key("routing"), Seg::Index(i), key("when")],
+                format!("routing[{}] uses {}", i, unknown_field(name)),
+            );
+        }
+    }
+    if let Some(codeowners) = &schema.codeowners {
+        if !ids.contains(codeowners.field.as_str()) {
+            findings.warning(
+                vec![key("codeowners"), key("field")],
+                format!("codeowners uses {}", unknown_field(&codeowners.field)),
+            );
+        }
+    }
+}
+
+fn check_fields(fields: &[FieldDefinition], list: &str, findings: &mut Findings) {
+    let mut seen = HashSet::new();
+    for (i, field) in fields.iter().enumerate() {
+        let path = |k: &str| vec![key(list), Seg::Index(i), key(k)];
+
+        if !seen.insert(field.id.as_str()) {
+            findings.error(
+                path("id"),
+                format!("duplicate field id `{}` in {}", field.id, list),
+            );
+        }
+        if field.field_type == FieldType::Select && field.options.is_empty() {
+            findings.error(
+                path("type"),
+                format!("select field `{}` has no options", field.id),
+            );
+        }
+        if field.field_type != FieldType::Number && (field.min.is_some() || field.max.is_some()) {
+            let k = if field.min.is_some() { "min" } else { "max" };
+            findings.warning(
+                path(k),
+                format!("`{}` only applies to number fields, not `{}`", k, field.id),
+            );
+        }
+        if let (Some(min), Some(max)) = (field.min, field.max) {
+            if min > max {
+                findings.error(
+                    path("min"),
+                    format!(
+                        "min {} of field `{}` is greater than its max {}",
+                        min, field.id, max
+                    ),
+                );
+            }
+        }
+        if let Some(pattern) = &field.pattern {
+            if let Err(e) = Regex::new(pattern) {
+                let reason = e.to_string();
+                let reason = reason.lines().last().unwrap_or_default().trim();
+                findings.error(
+                    path("pattern"),
+                    format!("invalid regex `{}`: {}", pattern, reason),
+                );
+            }
+        }
+        if let Some(default) = &field.default {
+            if let Err(e) = check_value(field, default) {
+                findings.error(path("default"), format!("default: {}", e));
+            }
+        }
+        if let Some(default) = &field.default {
+            if !field.options.is_empty() && !field.options.contains(default) {
+                findings.error(
+                    path("default"),
+                    format!(
+                        "default `{}` of field `{}` is not one of its options ({})",
+                        default,
+                        field.id,
+                        field.options.join(", ")
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// The candidate closest to `name`, if any is close enough to be a typo.
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|c| (edit_distance(&name.to_lowercase(), &c.to_lowercase()), c))
+        .filter(|(d, _)| *d <= max)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
+
+/// Edit distance counting a swap of adjacent characters as one edit, the
+/// most common typo in key names.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// A non-blank, non-comment source line.
+#[derive(Clone)]
+struct Line<'a> {
+    number: usize,
+    column: usize,
+    text: &'a str,
+}
+
+/// Find the 1-based line and column of `path` in block-style YAML. Falls back
+/// to the deepest part of the path that could be found (e.g. a key whose
+/// value is a flow sequence), or the start of the file.
+fn locate(source: &str, path: &[Seg]) -> (usize, usize) {
+    let mut scope: Vec<Line> = source
+        .lines()
+        .enumerate()
+        .filter_map(|(i, raw)| {
+            let text = raw.trim_start();
+            if text.is_empty() || text.starts_with('#') {
+                return None;
+            }
+            Some(Line {
+                number: i + 1,
+                column: raw.len() - text.len(),
+                text,
+            })
+        })
+        .collect();
+    let mut found = (1, 1);
+
+    for seg in path {
+        let Some(base) = scope.iter().map(|l| l.column).min() else {
+            break;
+        };
+        let at_base = |l: &Line| l.column == base;
+        let index = match seg {
+            Seg::Key(k) => scope
+                .iter()
+                .position(|l| at_base(l) && key_of(l.text) == Some(k.as_str())),
+            Seg::Index(n) => scope
+                .iter()
+                .enumerate()
+                .filter(|(_, l)| at_base(l) && is_item(l.text))
+                .nth(*n)
+                .map(|(i, _)| i),
+        };
+        let Some(index) = index else {
+            break;
+        };
+        let line = scope[index].clone();
+        found = (line.number, line.column + 1);
+
+        // Lines nested under this one; sequences may sit at the key's own indent
+        let rest = &scope[index + 1..];
+        let nested = |l: &Line| l.column > base;
+        let mut next: Vec<Line> = match seg {
+            Seg::Key(_) if rest.first().is_some_and(|l| at_base(l) && is_item(l.text)) => rest
+                .iter()
+                .take_while(|l| nested(l) || (at_base(l) && is_item(l.text)))
+                .cloned()
+                .collect(),
+            _ => rest.iter().take_while(|l| nested(l)).cloned().collect(),
+        };
+        // The first key of a sequence item shares the dash's line
+        if let Seg::Index(_) = seg {
+            let item = line.text[1..].trim_start();
+            if !item.is_empty() {
+                let column = line.column + (line.text.len() - item.len());
+                next.insert(
+                    0,
+                    Line {
+                        number: line.number,
+                        column,
+                        text: item,
+                    },
+                );
+            }
+        }
+        scope = next;
+    }
+
+    found
+}
+
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+fn key_of(text: &str) -> Option<&str> {
+    let (k, rest) = text.split_once(':')?;
+    if !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some(k.trim().trim_matches(|c| c == '"' || c == '\''))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::defaults::default_schema;
+
+    const SCHEMA: &str = "\
+repo: acme/app
+titel_template: x
+title_template: \"[{tool}] {sumary}\"
+fields:
+  - id: tool
+    type: select
+    options: [vim, emacs]
+    default: nano
+  - id: summary
+    lable: Summary
+  - id: tool
+    type: select
+redact:
+  patterns:
+    - \"ok-[0-9]+\"
+    - \"(unclosed\"
+";
+
+    fn messages(source: &str) -> Vec<(usize, usize, String)> {
+        lint(source)
+            .into_iter()
+            .map(|d| (d.line, d.column, d.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_reports_problems_with_positions() {
+        let found = messages(SCHEMA);
+        let at = |line, column| {
+            found
+                .iter()
+                .find(|(l, c, _)| (*l, *c) == (line, column))
+                .map(|(_, _, m)| m.as_str())
+                .unwrap_or_else(|| panic!("nothing at {}:{} in {:?}", line, column, found))
+        };
+        assert!(at(2, 1).contains("did you mean `title_template`"));
+        assert!(at(3, 1).contains("uses unknown field `sumary` (did you mean `summary`?)"));
+        assert!(at(8, 5).contains("default `nano`"));
+        assert!(at(10, 5).contains("unknown key `lable` in fields[1]"));
+        assert!(at(11, 5).contains("duplicate field id `tool`"));
+        assert!(at(12, 5).contains("has no options"));
+        assert!(at(16, 5).contains("invalid regex `(unclosed`"));
+        assert_eq!(found.len(), 7);
+    }
+
+    #[test]
+    fn test_syntax_and_type_errors() {
+        let found = lint("fields:\n  - id: a\n    required: maybe\n");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].line, 3);
+
+        let found = lint("fields: [\n");
+        assert_eq!(found[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_known_keys_cover_schema() {
+        let value = serde_yaml::to_value(default_schema()).unwrap();
+        for k in value.as_mapping().unwrap().keys() {
+            assert!(SCHEMA_KEYS.contains(&k.as_str().unwrap()), "{:?}", k);
+        }
+        let mut findings = Findings(Vec::new());
+        unknown_keys(&value, &mut findings);
+        assert!(findings.0.is_empty());
+    }
+
+    #[test]
+    fn test_number_bounds() {
+        let found = lint(
+            "fields:\n  - { id: count, type: number, min: 5, max: 1, default: \"9\" }\n  - { id: name, max: 3 }\n",
+        );
+        let messages: Vec<&str> = found.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert!(messages.iter().any(|m| m.contains("greater than its max")));
+        assert!(messages
+            .iter()
+            .any(|m| m.contains("'count' must be at most 1")));
+        assert!(messages
+            .iter()
+            .any(|m| m.contains("only applies to number fields")));
+    }
+
+    #[test]
+    fn test_closest() {
+        assert_eq!(closest("lable", FIELD_KEYS.iter().copied()), Some("label"));
+        assert_eq!(closest("colour", ["color"]), Some("color"));
+        assert_eq!(closest("xyz", FIELD_KEYS.iter().copied()), None);
+    }
+}
@@ -1,17 +1,37 @@
+mod attachments;
 mod body_formatter;
+mod cache;
+mod codeowners;
 mod commands;
 mod config;
+mod dates;
 mod defaults;
+mod error;
 mod gh;
 mod git;
+mod github_api;
 mod github_templates;
+mod history;
+mod json_schema;
+mod labels;
+mod lint;
+mod policy;
+mod redact;
+mod routing;
+mod template;
+mod title_template;
 mod types;
+mod values;
 
 use clap::{Parser, Subcommand};
 use colored::Colorize;
 
 #[derive(Parser)]
-#[command(name = "gripe", version, about = "Submit structured feedback as GitHub issues")]
+#[command(
+    name = "gripe",
+    version,
+    about = "Submit structured feedback as GitHub issues"
+)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
@@ -20,58 +40,122 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Submit feedback as a GitHub issue
-    Submit {
-        /// JSON string with field values
-        #[arg(long)]
-        json: Option<String>,
-        /// Read JSON from stdin
-        #[arg(long)]
-        stdin: bool,
-        /// Preview without creating an issue
-        #[arg(long)]
-        dry_run: bool,
-        /// Output result as JSON
+    Submit(commands::submit::SubmitArgs),
+    /// Add a comment to an existing issue
+    Comment(commands::comment::CommentArgs),
+    /// List issues you filed with gripe
+    List(commands::list::ListArgs),
+    /// Show submissions recorded on this machine
+    History {
+        /// Output as JSON
         #[arg(long)]
-        output_json: bool,
-        /// Target repository (owner/repo)
+        json: bool,
+        /// Only show submissions to this repository (owner/repo)
         #[arg(long)]
         repo: Option<String>,
-        /// Field values as key=value pairs
-        #[arg(trailing_var_arg = true)]
-        fields: Vec<String>,
+        /// Maximum number of entries to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
     },
+    /// Show the state of an issue you filed
+    Status(commands::status::StatusArgs),
     /// Create a gripe.yaml in the current directory
-    Init {
-        /// Overwrite existing gripe.yaml
-        #[arg(long)]
-        force: bool,
+    Init(commands::init::InitArgs),
+    /// Inspect automated-submission policies
+    Policy {
+        #[command(subcommand)]
+        action: PolicyAction,
     },
     /// Show the resolved schema
     Schema {
+        /// Output as JSON (same as --format json)
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+        /// Output format; json-schema describes the values `submit --json` accepts
+        #[arg(long, value_parser = ["text", "json", "json-schema"])]
+        format: Option<String>,
+        /// Show the schema published by another repository (owner/repo)
+        #[arg(long)]
+        repo: Option<String>,
+    },
+    /// Convert between gripe.yaml and a GitHub issue form
+    Convert(commands::convert::ConvertArgs),
+    /// Check a gripe.yaml for mistakes
+    Validate {
+        /// Path to the gripe.yaml; defaults to the one found from the current directory
+        path: Option<std::path::PathBuf>,
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
 }
 
+#[derive(Subcommand)]
+enum PolicyAction {
+    /// Show the local, remote and effective policy for a repo
+    Show {
+        /// Target repository (owner/repo); defaults to the resolved repo
+        repo: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Re-fetch the remote policy instead of using the cache
+        #[arg(long)]
+        refresh: bool,
+    },
+}
+
 fn main() {
     let cli = Cli::parse();
+    // Commands asked for JSON output report their errors as JSON too
+    let json_errors = match &cli.command {
+        Commands::Submit(args) => args.output_json,
+        Commands::Comment(args) => args.output_json,
+        Commands::List(args) => args.json,
+        Commands::Status(args) => args.json,
+        Commands::History { json, .. } => *json,
+        Commands::Policy {
+            action: PolicyAction::Show { json, .. },
+        } => *json,
+        Commands::Schema { json, format, .. } => {
+            *json || format.as_deref().is_some_and(|f| f.starts_with("json"))
+        }
+        Commands::Validate { json, .. } => *json,
+        Commands::Init(_) | Commands::Convert(_) => false,
+    };
+    // `validate --json` puts lint failures in its report instead
+    let reported = matches!(&cli.command, Commands::Validate { json: true, .. });
 
     let result = match cli.command {
-        Commands::Submit {
-            json,
-            stdin,
-            dry_run,
-            output_json,
-            repo,
-            fields,
-        } => commands::submit::run(json, stdin, dry_run, output_json, repo, fields),
-        Commands::Init { force } => commands::init::run(force),
-        Commands::Schema { json } => commands::schema::run(json),
+        Commands::Submit(args) => commands::submit::run(args),
+        Commands::Comment(args) => commands::comment::run(args),
+        Commands::List(args) => commands::list::run(args),
+        Commands::History { json, repo, limit } => commands::history::run(json, repo, limit),
+        Commands::Status(args) => commands::status::run(args),
+        Commands::Init(args) => commands::init::run(args),
+        Commands::Policy { action } => match action {
+            PolicyAction::Show {
+                repo,
+                json,
+                refresh,
+            } => commands::policy::show(repo, json, refresh),
+        },
+        Commands::Schema { json, format, repo } => {
+            let format = format.unwrap_or_else(|| if json { "json" } else { "text" }.to_string());
+            commands::schema::run(&format, repo)
+        }
+        Commands::Convert(args) => commands::convert::run(args),
+        Commands::Validate { path, json } => commands::validate::run(path, json),
     };
 
     if let Err(e) = result {
-        eprintln!("{} {}", "error:".red().bold(), e);
-        std::process::exit(1);
+        if reported && matches!(e, error::GripeError::Validation(_)) {
+            // Already in the report
+        } else if json_errors {
+            println!("{}", e.to_json());
+        } else {
+            eprintln!("{} {}", "error:".red().bold(), e);
+        }
+        std::process::exit(e.exit_code());
     }
 }
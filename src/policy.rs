@@ -0,0 +1,526 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::config::SchemaSource;
+use crate::error::GripeError;
+use crate::gh;
+use crate::types::{AutomatedMode, AutomatedPolicy, GripeSchema, ReviewTarget};
+
+/// Outcome of checking an automated submission against a policy.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    Allow,
+    Deny(String),
+    /// Accepted, but must be filed at the review target instead.
+    Review(ReviewTarget),
+}
+
+/// Check an automated submission against `policy`.
+///
+/// `count_today` is only called when the policy has a rate limit, since it
+/// costs an API request.
+pub fn evaluate<F>(
+    policy: &AutomatedPolicy,
+    agent: Option<&str>,
+    values: &HashMap<String, String>,
+    count_today: F,
+) -> Result<Decision, GripeError>
+where
+    F: FnOnce() -> Result<usize, GripeError>,
+{
+    if policy.mode == AutomatedMode::Deny {
+        return Ok(Decision::Deny(
+            "This repository does not accept automated feedback".to_string(),
+        ));
+    }
+
+    let matches = |list: &[String], agent: &str| list.iter().any(|a| a.eq_ignore_ascii_case(agent));
+
+    if let Some(agent) = agent {
+        if matches(&policy.blocked_agents, agent) {
+            return Ok(Decision::Deny(format!(
+                "Agent '{}' is blocked by this repository",
+                agent
+            )));
+        }
+    }
+
+    if !policy.allowed_agents.is_empty() {
+        match agent {
+            Some(agent) if matches(&policy.allowed_agents, agent) => {}
+            Some(agent) => {
+                return Ok(Decision::Deny(format!(
+                    "Agent '{}' is not in this repository's allowed agents ({})",
+                    agent,
+                    policy.allowed_agents.join(", ")
+                )));
+            }
+            None => {
+                return Ok(Decision::Deny(
+                    "This repository only accepts automated feedback from identified agents. Pass --agent or set GRIPE_AGENT.".to_string(),
+                ));
+            }
+        }
+    }
+
+    if policy.require_reporter
+        && values
+            .get("reporter")
+            .map(|v| v.trim().is_empty())
+            .unwrap_or(true)
+    {
+        return Ok(Decision::Deny(
+            "This repository requires a 'reporter' field on automated feedback".to_string(),
+        ));
+    }
+
+    if let Some(limit) = policy.rate_limit {
+        let count = count_today()?;
+        if count >= limit as usize {
+            return Ok(Decision::Deny(format!(
+                "Daily limit of {} automated issues reached; try again tomorrow",
+                limit
+            )));
+        }
+    }
+
+    match policy.mode {
+        AutomatedMode::Review => match &policy.review {
+            Some(target) if target.repo.is_some() || target.discussion_category.is_some() => {
+                Ok(Decision::Review(target.clone()))
+            }
+            _ => Err(GripeError::ConfigParse(
+                "automated mode 'review' requires review.repo or review.discussion_category"
+                    .to_string(),
+            )),
+        },
+        _ => Ok(Decision::Allow),
+    }
+}
+
+/// Where a policy was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolicySource {
+    /// The locally resolved schema (gripe.yaml, issue templates or default).
+    Local(String),
+    /// gripe.yaml in the target repo.
+    Remote(String),
+}
+
+impl fmt::Display for PolicySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicySource::Local(source) => write!(f, "local policy ({})", source),
+            PolicySource::Remote(repo) => write!(f, "remote policy ({}/gripe.yaml)", repo),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SourcedPolicy {
+    pub policy: AutomatedPolicy,
+    pub source: PolicySource,
+}
+
+/// Collect the policies that apply to a submission to `repo`: the target
+/// repo's remote gripe.yaml (if any) and the locally resolved schema.
+/// `refresh` skips the remote policy cache.
+pub fn load_policies(
+    schema: &GripeSchema,
+    source: &SchemaSource,
+    repo: &str,
+    refresh: bool,
+) -> Result<Vec<SourcedPolicy>, GripeError> {
+    let mut policies = Vec::new();
+
+    if let Some(remote) = gh::check_robots(repo, refresh)? {
+        policies.push(SourcedPolicy {
+            policy: remote,
+            source: PolicySource::Remote(repo.to_string()),
+        });
+    }
+    policies.push(SourcedPolicy {
+        policy: schema.automated.clone(),
+        source: PolicySource::Local(source.to_string()),
+    });
+
+    Ok(policies)
+}
+
+/// Where an automated submission to a repo ends up, and so where its rate
+/// limit is counted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FiledTo {
+    Issues(String),
+    Discussions { repo: String, category: String },
+}
+
+/// Where a submission to `repo` is filed under `policies`: the review target
+/// of the first policy in review mode, or `repo` itself.
+pub fn filed_to(policies: &[SourcedPolicy], repo: &str) -> FiledTo {
+    let review = policies
+        .iter()
+        .find(|p| p.policy.mode == AutomatedMode::Review)
+        .and_then(|p| p.policy.review.as_ref());
+    match review {
+        Some(ReviewTarget {
+            repo: Some(triage), ..
+        }) => FiledTo::Issues(triage.clone()),
+        Some(ReviewTarget {
+            discussion_category: Some(category),
+            ..
+        }) => FiledTo::Discussions {
+            repo: repo.to_string(),
+            category: category.clone(),
+        },
+        _ => FiledTo::Issues(repo.to_string()),
+    }
+}
+
+/// Check a submission to `repo` against every policy in turn. The first
+/// policy to deny or redirect the submission decides, and its source is
+/// returned with it. `count_today` counts today's submissions where this
+/// one would be filed.
+pub fn evaluate_all<F>(
+    policies: &[SourcedPolicy],
+    repo: &str,
+    agent: Option<&str>,
+    values: &HashMap<String, String>,
+    mut count_today: F,
+) -> Result<(Decision, Option<PolicySource>), GripeError>
+where
+    F: FnMut(&FiledTo) -> Result<usize, GripeError>,
+{
+    let target = filed_to(policies, repo);
+    let mut count = None;
+    let mut review = None;
+
+    for sourced in policies {
+        let decision = evaluate(&sourced.policy, agent, values, || match count {
+            Some(n) => Ok(n),
+            None => {
+                let n = count_today(&target)?;
+                count = Some(n);
+                Ok(n)
+            }
+        })
+        .map_err(|e| match e {
+            GripeError::ConfigParse(m) => {
+                GripeError::ConfigParse(format!("{}: {}", sourced.source, m))
+            }
+            other => other,
+        })?;
+
+        match decision {
+            Decision::Allow => {}
+            Decision::Deny(_) => return Ok((decision, Some(sourced.source.clone()))),
+            Decision::Review(_) => {
+                review.get_or_insert((decision, Some(sourced.source.clone())));
+            }
+        }
+    }
+
+    Ok(review.unwrap_or((Decision::Allow, None)))
+}
+
+/// Combine policies into the single strictest policy they imply.
+pub fn merge(policies: &[&AutomatedPolicy]) -> AutomatedPolicy {
+    let mut merged = AutomatedPolicy::default();
+
+    for policy in policies {
+        merged.mode = match (&merged.mode, &policy.mode) {
+            (AutomatedMode::Deny, _) | (_, AutomatedMode::Deny) => AutomatedMode::Deny,
+            (AutomatedMode::Review, _) | (_, AutomatedMode::Review) => AutomatedMode::Review,
+            _ => AutomatedMode::Allow,
+        };
+        merged.rate_limit = match (merged.rate_limit, policy.rate_limit) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        for label in &policy.labels {
+            if !merged.labels.contains(label) {
+                merged.labels.push(label.clone());
+            }
+        }
+        merged.allowed_agents = match (
+            merged.allowed_agents.is_empty(),
+            policy.allowed_agents.is_empty(),
+        ) {
+            (_, true) => merged.allowed_agents,
+            (true, false) => policy.allowed_agents.clone(),
+            (false, false) => {
+                let both: Vec<String> = merged
+                    .allowed_agents
+                    .into_iter()
+                    .filter(|a| {
+                        policy
+                            .allowed_agents
+                            .iter()
+                            .any(|b| b.eq_ignore_ascii_case(a))
+                    })
+                    .collect();
+                // An empty list means any agent, so no agent in common has
+                // to be written as deny
+                if both.is_empty() {
+                    merged.mode = AutomatedMode::Deny;
+                }
+                both
+            }
+        };
+        for agent in &policy.blocked_agents {
+            if !merged.blocked_agents.contains(agent) {
+                merged.blocked_agents.push(agent.clone());
+            }
+        }
+        merged.require_reporter |= policy.require_reporter;
+        if merged.review.is_none() && policy.mode == AutomatedMode::Review {
+            merged.review = policy.review.clone();
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_count() -> Result<usize, GripeError> {
+        panic!("rate limit should not be checked")
+    }
+
+    #[test]
+    fn test_deny_mode() {
+        let policy = AutomatedPolicy::with_mode(AutomatedMode::Deny);
+        let decision = evaluate(&policy, None, &HashMap::new(), no_count).unwrap();
+        assert!(matches!(decision, Decision::Deny(_)));
+    }
+
+    #[test]
+    fn test_agent_lists() {
+        let policy = AutomatedPolicy {
+            allowed_agents: vec!["ci-bot".to_string()],
+            blocked_agents: vec!["spammer".to_string()],
+            ..Default::default()
+        };
+        let values = HashMap::new();
+        assert_eq!(
+            evaluate(&policy, Some("CI-Bot"), &values, no_count).unwrap(),
+            Decision::Allow
+        );
+        assert!(matches!(
+            evaluate(&policy, Some("other"), &values, no_count).unwrap(),
+            Decision::Deny(_)
+        ));
+        assert!(matches!(
+            evaluate(&policy, None, &values, no_count).unwrap(),
+            Decision::Deny(_)
+        ));
+        assert!(matches!(
+            evaluate(&policy, Some("spammer"), &values, no_count).unwrap(),
+            Decision::Deny(_)
+        ));
+    }
+
+    #[test]
+    fn test_require_reporter() {
+        let policy = AutomatedPolicy {
+            require_reporter: true,
+            ..Default::default()
+        };
+        let mut values = HashMap::new();
+        assert!(matches!(
+            evaluate(&policy, None, &values, no_count).unwrap(),
+            Decision::Deny(_)
+        ));
+        values.insert("reporter".to_string(), "nightly-agent".to_string());
+        assert_eq!(
+            evaluate(&policy, None, &values, no_count).unwrap(),
+            Decision::Allow
+        );
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let policy = AutomatedPolicy {
+            rate_limit: Some(5),
+            ..Default::default()
+        };
+        let values = HashMap::new();
+        assert_eq!(
+            evaluate(&policy, None, &values, || Ok(4)).unwrap(),
+            Decision::Allow
+        );
+        assert!(matches!(
+            evaluate(&policy, None, &values, || Ok(5)).unwrap(),
+            Decision::Deny(_)
+        ));
+    }
+
+    #[test]
+    fn test_review_mode_needs_target() {
+        let mut policy = AutomatedPolicy::with_mode(AutomatedMode::Review);
+        assert!(evaluate(&policy, None, &HashMap::new(), no_count).is_err());
+
+        let target = ReviewTarget {
+            repo: Some("owner/triage".to_string()),
+            discussion_category: None,
+        };
+        policy.review = Some(target.clone());
+        assert_eq!(
+            evaluate(&policy, None, &HashMap::new(), no_count).unwrap(),
+            Decision::Review(target)
+        );
+    }
+
+    #[test]
+    fn test_evaluate_all_reports_denying_source() {
+        let policies = vec![
+            SourcedPolicy {
+                policy: AutomatedPolicy::with_mode(AutomatedMode::Deny),
+                source: PolicySource::Local("gripe.yaml".to_string()),
+            },
+            SourcedPolicy {
+                policy: AutomatedPolicy::default(),
+                source: PolicySource::Remote("o/r".to_string()),
+            },
+        ];
+        let (decision, source) =
+            evaluate_all(&policies, "o/r", None, &HashMap::new(), |_| no_count()).unwrap();
+        assert!(matches!(decision, Decision::Deny(_)));
+        assert_eq!(source, Some(PolicySource::Local("gripe.yaml".to_string())));
+    }
+
+    #[test]
+    fn test_evaluate_all_counts_once() {
+        let limited = AutomatedPolicy {
+            rate_limit: Some(10),
+            ..Default::default()
+        };
+        let policies: Vec<_> = ["a", "b"]
+            .iter()
+            .map(|r| SourcedPolicy {
+                policy: limited.clone(),
+                source: PolicySource::Remote(r.to_string()),
+            })
+            .collect();
+        let mut calls = 0;
+        let (decision, _) = evaluate_all(&policies, "o/r", None, &HashMap::new(), |_| {
+            calls += 1;
+            Ok(1)
+        })
+        .unwrap();
+        assert_eq!(decision, Decision::Allow);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_review_mode_counts_where_it_files() {
+        let mut review = AutomatedPolicy {
+            mode: AutomatedMode::Review,
+            rate_limit: Some(5),
+            review: Some(ReviewTarget {
+                repo: Some("o/triage".to_string()),
+                discussion_category: None,
+            }),
+            ..Default::default()
+        };
+        let policies = |policy: &AutomatedPolicy| {
+            vec![
+                SourcedPolicy {
+                    policy: AutomatedPolicy::default(),
+                    source: PolicySource::Local("gripe.yaml".to_string()),
+                },
+                SourcedPolicy {
+                    policy: policy.clone(),
+                    source: PolicySource::Remote("o/r".to_string()),
+                },
+            ]
+        };
+
+        let mut counted = None;
+        let (decision, _) = evaluate_all(&policies(&review), "o/r", None, &HashMap::new(), |t| {
+            counted = Some(t.clone());
+            Ok(5)
+        })
+        .unwrap();
+        assert!(matches!(decision, Decision::Deny(_)));
+        assert_eq!(counted, Some(FiledTo::Issues("o/triage".to_string())));
+
+        review.review = Some(ReviewTarget {
+            repo: None,
+            discussion_category: Some("Triage".to_string()),
+        });
+        assert_eq!(
+            filed_to(&policies(&review), "o/r"),
+            FiledTo::Discussions {
+                repo: "o/r".to_string(),
+                category: "Triage".to_string()
+            }
+        );
+        assert_eq!(
+            filed_to(&policies(&AutomatedPolicy::default()), "o/r"),
+            FiledTo::Issues("o/r".to_string())
+        );
+    }
+
+    #[test]
+    fn test_merge_is_strictest() {
+        let a = AutomatedPolicy {
+            rate_limit: Some(10),
+            labels: vec!["bot".to_string()],
+            allowed_agents: vec!["x".to_string(), "y".to_string()],
+            ..Default::default()
+        };
+        let b = AutomatedPolicy {
+            mode: AutomatedMode::Deny,
+            rate_limit: Some(3),
+            labels: vec!["bot".to_string(), "auto".to_string()],
+            allowed_agents: vec!["y".to_string()],
+            require_reporter: true,
+            ..Default::default()
+        };
+        let merged = merge(&[&a, &b]);
+        assert_eq!(merged.mode, AutomatedMode::Deny);
+        assert_eq!(merged.rate_limit, Some(3));
+        assert_eq!(merged.labels, vec!["bot", "auto"]);
+        assert_eq!(merged.allowed_agents, vec!["y"]);
+        assert!(merged.require_reporter);
+    }
+
+    #[test]
+    fn test_merge_disjoint_allowed_agents_denies() {
+        let a = AutomatedPolicy {
+            allowed_agents: vec!["x".to_string()],
+            ..Default::default()
+        };
+        let b = AutomatedPolicy {
+            allowed_agents: vec!["y".to_string()],
+            ..Default::default()
+        };
+        let merged = merge(&[&a, &b]);
+        assert_eq!(merged.mode, AutomatedMode::Deny);
+        assert!(matches!(
+            evaluate(&merged, Some("x"), &HashMap::new(), no_count).unwrap(),
+            Decision::Deny(_)
+        ));
+    }
+
+    #[test]
+    fn test_policy_short_and_table_forms() {
+        let schema: crate::types::GripeSchema =
+            serde_yaml::from_str("automated: deny\nfields: []").unwrap();
+        assert_eq!(schema.automated.mode, AutomatedMode::Deny);
+
+        let yaml = "automated:\n  mode: review\n  rate_limit: 10\n  review:\n    repo: o/triage\nfields: []";
+        let schema: crate::types::GripeSchema = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(schema.automated.rate_limit, Some(10));
+        assert!(serde_yaml::to_string(&schema)
+            .unwrap()
+            .contains("mode: review"));
+
+        let both = "automated:\n  mode: review\n  review:\n    repo: o/triage\n    discussion_category: Triage\nfields: []";
+        let err = serde_yaml::from_str::<crate::types::GripeSchema>(both).unwrap_err();
+        assert!(err.to_string().contains("not both"));
+    }
+}
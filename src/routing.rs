@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use crate::gh::IssueMeta;
+use crate::labels::derive_labels;
+use crate::types::{GripeSchema, RoutingRule};
+
+/// Work out the labels, assignees, milestone, projects and type for a gripe
+/// from the schema's defaults, `labels_from` and any matching `routing` rules.
+pub fn resolve(schema: &GripeSchema, values: &HashMap<String, String>) -> IssueMeta {
+    let mut meta = IssueMeta {
+        milestone: schema.milestone.clone(),
+        issue_type: schema.issue_type.clone(),
+        ..Default::default()
+    };
+    extend_unique(&mut meta.labels, schema.labels.iter().cloned());
+    extend_unique(&mut meta.labels, derive_labels(&schema.labels_from, values));
+    extend_unique(
+        &mut meta.assignees,
+        schema.assignees.iter().map(|a| login(a)),
+    );
+    extend_unique(&mut meta.projects, schema.projects.iter().cloned());
+
+    for rule in schema.routing.iter().filter(|r| matches(r, values)) {
+        extend_unique(&mut meta.labels, rule.labels.iter().cloned());
+        extend_unique(&mut meta.assignees, rule.assignees.iter().map(|a| login(a)));
+        extend_unique(&mut meta.projects, rule.projects.iter().cloned());
+        if rule.milestone.is_some() {
+            meta.milestone = rule.milestone.clone();
+        }
+        if rule.issue_type.is_some() {
+            meta.issue_type = rule.issue_type.clone();
+        }
+    }
+
+    meta
+}
+
+/// Append the items not already in `list`.
+pub fn extend_unique(list: &mut Vec<String>, items: impl IntoIterator<Item = String>) {
+    for item in items {
+        if !list.contains(&item) {
+            list.push(item);
+        }
+    }
+}
+
+/// A GitHub login without the `@` people tend to write in front of it.
+/// gh's `@me` is kept as is.
+pub fn login(name: &str) -> String {
+    if name == "@me" {
+        return name.to_string();
+    }
+    name.trim_start_matches('@').to_string()
+}
+
+fn matches(rule: &RoutingRule, values: &HashMap<String, String>) -> bool {
+    rule.when.iter().all(|(field, expected)| {
+        values
+            .get(field)
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case(expected.trim()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(yaml: &str) -> GripeSchema {
+        serde_yaml::from_str(&format!("{}\nfields: []\n", yaml)).unwrap()
+    }
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_rules_add_and_override() {
+        let schema = schema(
+            "labels: [feedback]\nassignees: [\"@triager\"]\nmilestone: Backlog\ntype: Bug\nrouting:\n  - when: { tool: vim }\n    assignees: [\"@vim-owner\"]\n    labels: [editor]\n    milestone: \"1.0\"\n    projects: [acme/3]",
+        );
+
+        let meta = resolve(&schema, &values(&[("tool", "Vim")]));
+        assert_eq!(meta.labels, vec!["feedback", "editor"]);
+        assert_eq!(meta.assignees, vec!["triager", "vim-owner"]);
+        assert_eq!(meta.milestone.as_deref(), Some("1.0"));
+        assert_eq!(meta.projects, vec!["acme/3"]);
+        assert_eq!(meta.issue_type.as_deref(), Some("Bug"));
+
+        let meta = resolve(&schema, &values(&[("tool", "emacs")]));
+        assert_eq!(meta.assignees, vec!["triager"]);
+        assert_eq!(meta.milestone.as_deref(), Some("Backlog"));
+    }
+
+    #[test]
+    fn test_all_conditions_must_match() {
+        let schema = schema(
+            "routing:\n  - when: { tool: vim, severity: critical }\n    assignees: [oncall]",
+        );
+        assert!(resolve(&schema, &values(&[("tool", "vim")]))
+            .assignees
+            .is_empty());
+        assert_eq!(
+            resolve(
+                &schema,
+                &values(&[("tool", "vim"), ("severity", "critical")])
+            )
+            .assignees,
+            vec!["oncall"]
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+
+use crate::attachments::fenced;
+
+/// A field value made available to a body template.
+#[derive(Debug, Clone)]
+pub struct Var {
+    pub label: String,
+    pub value: String,
+}
+
+pub type Context = HashMap<String, Var>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Filter {
+    Code,
+    Quote,
+    Details,
+    Upper,
+    Lower,
+}
+
+impl Filter {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "code" => Ok(Filter::Code),
+            "quote" => Ok(Filter::Quote),
+            "details" => Ok(Filter::Details),
+            "upper" => Ok(Filter::Upper),
+            "lower" => Ok(Filter::Lower),
+            other => Err(format!(
+                "Unknown template filter '{}'. Expected one of: code, quote, details, upper, lower",
+                other
+            )),
+        }
+    }
+
+    fn apply(self, value: &str, label: &str) -> String {
+        match self {
+            Filter::Code => fenced(value, ""),
+            Filter::Quote => value
+                .lines()
+                .map(|l| {
+                    if l.is_empty() {
+                        ">".to_string()
+                    } else {
+                        format!("> {}", l)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Filter::Details => format!(
+                "<details>\n<summary>{}</summary>\n\n{}\n\n</details>",
+                label, value
+            ),
+            Filter::Upper => value.to_uppercase(),
+            Filter::Lower => value.to_lowercase(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Var {
+        name: String,
+        filters: Vec<Filter>,
+    },
+    If {
+        name: String,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Each {
+        name: String,
+        body: Vec<Node>,
+    },
+}
+
+/// A parsed body template.
+///
+/// Supports `{{field}}`, filters (`{{field | code}}`), conditionals
+/// (`{{#if field}}...{{else}}...{{/if}}`) and loops over multi-value fields
+/// (`{{#each field}}- {{this}}{{/each}}`), where the items are the non-empty
+/// lines of the value.
+#[derive(Debug, Clone)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+enum Token {
+    Text(String),
+    Tag(String),
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = src;
+
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+        }
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| "Unclosed '{{' in body template".to_string())?;
+        tokens.push(Token::Tag(after[..end].trim().to_string()));
+        rest = &after[end + 2..];
+
+        // A block tag at the end of a line swallows the newline, so blocks
+        // can sit on their own lines without leaving blank lines behind.
+        if let Some(Token::Tag(tag)) = tokens.last() {
+            if tag.starts_with('#') || tag.starts_with('/') || tag == "else" {
+                rest = rest.strip_prefix('\n').unwrap_or(rest);
+            }
+        }
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+
+    Ok(tokens)
+}
+
+impl Template {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let tokens = tokenize(src)?;
+        let mut pos = 0;
+        let (nodes, end) = parse_nodes(&tokens, &mut pos)?;
+        if let Some(tag) = end {
+            return Err(format!("Unexpected '{{{{{}}}}}' in body template", tag));
+        }
+        Ok(Template { nodes })
+    }
+
+    pub fn render(&self, ctx: &Context) -> String {
+        render_nodes(&self.nodes, ctx, None)
+    }
+}
+
+/// Parse nodes until EOF or a closing/else tag, which is returned.
+fn parse_nodes(tokens: &[Token], pos: &mut usize) -> Result<(Vec<Node>, Option<String>), String> {
+    let mut nodes = Vec::new();
+
+    while *pos < tokens.len() {
+        let token = &tokens[*pos];
+        *pos += 1;
+
+        let tag = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text.clone()));
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+
+        if tag == "else" || tag.starts_with('/') {
+            return Ok((nodes, Some(tag.clone())));
+        }
+
+        if let Some(name) = tag.strip_prefix("#if ") {
+            let name = name.trim().to_string();
+            let (then, end) = parse_nodes(tokens, pos)?;
+            let otherwise = match end.as_deref() {
+                Some("/if") => Vec::new(),
+                Some("else") => {
+                    let (otherwise, end) = parse_nodes(tokens, pos)?;
+                    if end.as_deref() != Some("/if") {
+                        return Err(format!("Missing '{{{{/if}}}}' for '{{{{#if {}}}}}'", name));
+                    }
+                    otherwise
+                }
+                _ => return Err(format!("Missing '{{{{/if}}}}' for '{{{{#if {}}}}}'", name)),
+            };
+            nodes.push(Node::If {
+                name,
+                then,
+                otherwise,
+            });
+        } else if let Some(name) = tag.strip_prefix("#each ") {
+            let name = name.trim().to_string();
+            let (body, end) = parse_nodes(tokens, pos)?;
+            if end.as_deref() != Some("/each") {
+                return Err(format!(
+                    "Missing '{{{{/each}}}}' for '{{{{#each {}}}}}'",
+                    name
+                ));
+            }
+            nodes.push(Node::Each { name, body });
+        } else if tag.starts_with('#') {
+            return Err(format!("Unknown block '{{{{{}}}}}' in body template", tag));
+        } else {
+            let mut parts = tag.split('|').map(|p| p.trim());
+            let name = parts.next().unwrap_or("").to_string();
+            if name.is_empty() {
+                return Err("Empty '{{}}' in body template".to_string());
+            }
+            let filters = parts.map(Filter::parse).collect::<Result<Vec<_>, _>>()?;
+            nodes.push(Node::Var { name, filters });
+        }
+    }
+
+    Ok((nodes, None))
+}
+
+fn lookup<'a>(name: &str, ctx: &'a Context, item: Option<&'a str>) -> (&'a str, &'a str) {
+    if name == "this" {
+        if let Some(item) = item {
+            return (item, "");
+        }
+    }
+    ctx.get(name)
+        .map(|v| (v.value.as_str(), v.label.as_str()))
+        .unwrap_or(("", ""))
+}
+
+fn render_nodes(nodes: &[Node], ctx: &Context, item: Option<&str>) -> String {
+    let mut out = String::new();
+
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var { name, filters } => {
+                let (value, label) = lookup(name, ctx, item);
+                let mut value = value.to_string();
+                for filter in filters {
+                    value = filter.apply(&value, label);
+                }
+                out.push_str(&value);
+            }
+            Node::If {
+                name,
+                then,
+                otherwise,
+            } => {
+                let (value, _) = lookup(name, ctx, item);
+                let branch = if value.trim().is_empty() {
+                    otherwise
+                } else {
+                    then
+                };
+                out.push_str(&render_nodes(branch, ctx, item));
+            }
+            Node::Each { name, body } => {
+                let (value, _) = lookup(name, ctx, item);
+                for line in value.lines().filter(|l| !l.trim().is_empty()) {
+                    out.push_str(&render_nodes(body, ctx, Some(line)));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(pairs: &[(&str, &str)]) -> Context {
+        pairs
+            .iter()
+            .map(|(k, v)| {
+                (
+                    k.to_string(),
+                    Var {
+                        label: format!("{} label", k),
+                        value: v.to_string(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn render(src: &str, pairs: &[(&str, &str)]) -> String {
+        Template::parse(src).unwrap().render(&ctx(pairs))
+    }
+
+    #[test]
+    fn test_variables_and_filters() {
+        assert_eq!(
+            render(
+                "{{tool | upper}}: {{summary}}",
+                &[("tool", "vim"), ("summary", "x")]
+            ),
+            "VIM: x"
+        );
+        assert_eq!(
+            render("{{log | code}}", &[("log", "a\nb")]),
+            "```\na\nb\n```"
+        );
+        assert_eq!(
+            render("{{note|quote}}", &[("note", "a\n\nb")]),
+            "> a\n>\n> b"
+        );
+        assert_eq!(
+            render("{{log | details}}", &[("log", "trace")]),
+            "<details>\n<summary>log label</summary>\n\ntrace\n\n</details>"
+        );
+    }
+
+    #[test]
+    fn test_if_else_blocks_on_own_lines() {
+        let src = "{{#if context}}\nContext: {{context}}\n{{else}}\nNo context\n{{/if}}\nend";
+        assert_eq!(render(src, &[("context", "ci")]), "Context: ci\nend");
+        assert_eq!(render(src, &[]), "No context\nend");
+    }
+
+    #[test]
+    fn test_each_over_lines() {
+        let src = "{{#each steps}}\n- {{this}}\n{{/each}}";
+        assert_eq!(
+            render(src, &[("steps", "open\n\nsave\n")]),
+            "- open\n- save\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{{#if a}}x").is_err());
+        assert!(Template::parse("{{a | shout}}").is_err());
+        assert!(Template::parse("{{a").is_err());
+        assert!(Template::parse("x{{/each}}").is_err());
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+
+/// GitHub rejects issue titles longer than this many characters.
+pub const MAX_TITLE_CHARS: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Default(String),
+    Truncate(usize),
+    Upper,
+    Lower,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field {
+        name: String,
+        filters: Vec<Filter>,
+    },
+    /// `{?...}` — rendered only when every field inside has a value.
+    Optional(Vec<Part>),
+}
+
+/// A parsed `title_template`.
+///
+/// `{field}` inserts a value, with optional filters: `{field|default:"none"}`,
+/// `{field|truncate:60}`, `{field|upper}`, `{field|lower}`. `{?[{tool}] }`
+/// is an optional segment dropped when any field in it is empty, and `\{`,
+/// `\}` and `\\` are literal characters.
+#[derive(Debug, Clone)]
+pub struct TitleTemplate {
+    parts: Vec<Part>,
+}
+
+impl TitleTemplate {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let chars: Vec<char> = src.chars().collect();
+        let mut pos = 0;
+        let parts = parse_parts(&chars, &mut pos, false)?;
+        Ok(TitleTemplate { parts })
+    }
+
+    /// Render the title, collapsing whitespace and enforcing GitHub's length limit.
+    pub fn render(&self, values: &HashMap<String, String>) -> String {
+        let (raw, _) = render_parts(&self.parts, values);
+        let title = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+        truncate(&title, MAX_TITLE_CHARS)
+    }
+
+    /// Names of the fields the template refers to.
+    pub fn fields(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        collect_fields(&self.parts, &mut names);
+        names
+    }
+}
+
+fn collect_fields<'a>(parts: &'a [Part], names: &mut Vec<&'a str>) {
+    for part in parts {
+        match part {
+            Part::Field { name, .. } => names.push(name),
+            Part::Optional(inner) => collect_fields(inner, names),
+            Part::Text(_) => {}
+        }
+    }
+}
+
+fn parse_parts(chars: &[char], pos: &mut usize, nested: bool) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+
+    while *pos < chars.len() {
+        let c = chars[*pos];
+        *pos += 1;
+        match c {
+            '\\' if *pos < chars.len() => {
+                text.push(chars[*pos]);
+                *pos += 1;
+            }
+            '}' if nested => {
+                if !text.is_empty() {
+                    parts.push(Part::Text(text));
+                }
+                return Ok(parts);
+            }
+            '}' => {
+                return Err("Unmatched '}' in title_template (use \\} for a literal brace)".into())
+            }
+            '{' => {
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                if chars.get(*pos) == Some(&'?') {
+                    *pos += 1;
+                    let inner = parse_parts(chars, pos, true)?;
+                    parts.push(Part::Optional(inner));
+                } else {
+                    parts.push(parse_field(chars, pos)?);
+                }
+            }
+            _ => text.push(c),
+        }
+    }
+
+    if nested {
+        return Err("Unclosed '{?' in title_template".into());
+    }
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    Ok(parts)
+}
+
+/// Parse `name|filter:arg|...}` after an opening brace.
+fn parse_field(chars: &[char], pos: &mut usize) -> Result<Part, String> {
+    let mut segments = vec![String::new()];
+    let mut in_quotes = false;
+
+    loop {
+        let c = *chars
+            .get(*pos)
+            .ok_or("Unclosed '{' in title_template (use \\{ for a literal brace)")?;
+        *pos += 1;
+        match c {
+            '\\' if in_quotes => {
+                if let Some(&next) = chars.get(*pos) {
+                    segments.last_mut().unwrap().push(next);
+                    *pos += 1;
+                }
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                segments.last_mut().unwrap().push(c);
+            }
+            '|' if !in_quotes => segments.push(String::new()),
+            '}' if !in_quotes => break,
+            _ => segments.last_mut().unwrap().push(c),
+        }
+    }
+
+    let name = segments[0].trim().to_string();
+    if name.is_empty() {
+        return Err("Empty '{}' placeholder in title_template".into());
+    }
+    let filters = segments[1..]
+        .iter()
+        .map(|s| parse_filter(s.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Part::Field { name, filters })
+}
+
+fn parse_filter(spec: &str) -> Result<Filter, String> {
+    let (name, arg) = match spec.split_once(':') {
+        Some((n, a)) => (n.trim(), Some(a.trim())),
+        None => (spec, None),
+    };
+
+    match (name, arg) {
+        ("default", Some(arg)) => {
+            let unquoted = arg
+                .strip_prefix('"')
+                .and_then(|a| a.strip_suffix('"'))
+                .unwrap_or(arg);
+            Ok(Filter::Default(unquoted.to_string()))
+        }
+        ("truncate", Some(arg)) => match arg.parse() {
+            // Room for at least one character before the ellipsis
+            Ok(n) if n >= 2 => Ok(Filter::Truncate(n)),
+            Ok(n) => Err(format!("truncate needs a width of at least 2, got {}", n)),
+            Err(_) => Err(format!("truncate expects a number, got '{}'", arg)),
+        },
+        ("upper", None) => Ok(Filter::Upper),
+        ("lower", None) => Ok(Filter::Lower),
+        _ => Err(format!(
+            "Unknown title filter '{}'. Expected default:\"...\", truncate:N, upper or lower",
+            spec
+        )),
+    }
+}
+
+/// Render parts, returning the text and whether every field had a value.
+fn render_parts(parts: &[Part], values: &HashMap<String, String>) -> (String, bool) {
+    let mut out = String::new();
+    let mut complete = true;
+    let mut skip_close = None;
+
+    for (i, part) in parts.iter().enumerate() {
+        match part {
+            Part::Text(text) => {
+                let text = match skip_close.take() {
+                    Some(close) => text.strip_prefix(close).unwrap_or(text),
+                    None => text,
+                };
+                out.push_str(text);
+            }
+            Part::Field { name, filters } => {
+                let value = render_field(name, filters, values);
+                if value.is_empty() {
+                    complete = false;
+                    // Drop brackets that would otherwise be left empty, e.g. "[] summary"
+                    let close = match out.chars().last() {
+                        Some('[') => Some(']'),
+                        Some('(') => Some(')'),
+                        _ => None,
+                    };
+                    let next = match parts.get(i + 1) {
+                        Some(Part::Text(t)) => t.chars().next(),
+                        _ => None,
+                    };
+                    if close.is_some() && close == next {
+                        out.pop();
+                        skip_close = close;
+                    }
+                }
+                out.push_str(&value);
+            }
+            Part::Optional(inner) => {
+                let (text, inner_complete) = render_parts(inner, values);
+                if inner_complete {
+                    out.push_str(&text);
+                }
+            }
+        }
+    }
+
+    (out, complete)
+}
+
+fn render_field(name: &str, filters: &[Filter], values: &HashMap<String, String>) -> String {
+    // Titles are single-line, so newlines and runs of spaces in values collapse
+    let mut value = values
+        .get(name)
+        .map(|v| v.split_whitespace().collect::<Vec<_>>().join(" "))
+        .unwrap_or_default();
+
+    for filter in filters {
+        value = match filter {
+            Filter::Default(d) if value.is_empty() => d.clone(),
+            Filter::Default(_) => value,
+            Filter::Truncate(n) => truncate(&value, *n),
+            Filter::Upper => value.to_uppercase(),
+            Filter::Lower => value.to_lowercase(),
+        };
+    }
+
+    value
+}
+
+fn truncate(value: &str, max: usize) -> String {
+    if value.chars().count() <= max {
+        return value.to_string();
+    }
+    let mut cut: String = value.chars().take(max.saturating_sub(1)).collect();
+    cut = cut.trim_end().to_string();
+    cut.push('…');
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, pairs: &[(&str, &str)]) -> String {
+        let values = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        TitleTemplate::parse(template).unwrap().render(&values)
+    }
+
+    #[test]
+    fn test_braces_in_values_are_preserved() {
+        assert_eq!(
+            render(
+                "[{tool}] {summary}",
+                &[("tool", "rust"), ("summary", "fn {x} fails")]
+            ),
+            "[rust] fn {x} fails"
+        );
+    }
+
+    #[test]
+    fn test_empty_brackets_are_dropped() {
+        assert_eq!(render("[{tool}] {summary}", &[("summary", "s")]), "s");
+    }
+
+    #[test]
+    fn test_default_and_truncate() {
+        assert_eq!(
+            render(
+                "[{tool|default:\"unknown\"}] {summary|truncate:8}",
+                &[("summary", "a long summary")]
+            ),
+            "[unknown] a long…"
+        );
+    }
+
+    #[test]
+    fn test_optional_segment() {
+        let tmpl = "{summary}{? ({severity|upper})}";
+        assert_eq!(render(tmpl, &[("summary", "s")]), "s");
+        assert_eq!(
+            render(tmpl, &[("summary", "s"), ("severity", "high")]),
+            "s (HIGH)"
+        );
+    }
+
+    #[test]
+    fn test_escapes_and_errors() {
+        assert_eq!(render("\\{{tool}\\}", &[("tool", "x")]), "{x}");
+        assert!(TitleTemplate::parse("{tool").is_err());
+        assert!(TitleTemplate::parse("tool}").is_err());
+        assert!(TitleTemplate::parse("{tool|shout}").is_err());
+        assert!(TitleTemplate::parse("{?x").is_err());
+        assert!(TitleTemplate::parse("{tool|truncate:0}").is_err());
+        assert!(TitleTemplate::parse("{tool|truncate:1}").is_err());
+        assert_eq!(render("{tool|truncate:2}", &[("tool", "abc")]), "a…");
+    }
+
+    #[test]
+    fn test_max_length_and_newlines() {
+        let long = "word ".repeat(100);
+        let title = render("{summary}", &[("summary", &long)]);
+        assert!(title.chars().count() <= MAX_TITLE_CHARS);
+        assert!(title.ends_with('…'));
+        assert_eq!(render("{summary}", &[("summary", "a\nb")]), "a b");
+    }
+}
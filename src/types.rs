@@ -1,27 +1,283 @@
-use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GripeSchema {
+    /// Identifies the schema in filed issues, for `gripe list --schema`.
+    #[serde(default)]
+    pub name: Option<String>,
     #[serde(default)]
     pub repo: Option<String>,
-    #[serde(default = "default_automated")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_policy",
+        serialize_with = "serialize_policy"
+    )]
     pub automated: AutomatedPolicy,
     #[serde(default)]
     pub labels: Vec<String>,
+    /// Labels derived from field values, keyed by field id.
+    #[serde(default)]
+    pub labels_from: BTreeMap<String, LabelRule>,
+    /// What to do with labels that don't exist on the target repo.
+    #[serde(default)]
+    pub missing_labels: MissingLabels,
+    /// Colors and descriptions used when creating missing labels.
+    #[serde(default)]
+    pub label_definitions: BTreeMap<String, LabelDefinition>,
+    #[serde(default)]
+    pub assignees: Vec<String>,
+    /// Milestone title.
+    #[serde(default)]
+    pub milestone: Option<String>,
+    /// Projects new issues are added to, as `owner/number`.
+    #[serde(default)]
+    pub projects: Vec<String>,
+    /// GitHub issue type, such as `Bug`.
+    #[serde(default, rename = "type")]
+    pub issue_type: Option<String>,
+    /// Extra labels, assignees and so on for gripes with matching field values.
+    #[serde(default)]
+    pub routing: Vec<RoutingRule>,
+    /// Assign gripes to the CODEOWNERS of a path taken from a field.
+    #[serde(default)]
+    pub codeowners: Option<CodeOwnersConfig>,
     #[serde(default)]
     pub title_template: Option<String>,
+    #[serde(default)]
+    pub body_template: Option<BodyTemplate>,
+    #[serde(default)]
+    pub redact: RedactConfig,
+    /// What to do with submitted values whose key isn't a field id.
+    #[serde(default)]
+    pub extra_fields: ExtraFields,
     pub fields: Vec<FieldDefinition>,
+    /// Fields collected by `gripe comment`; without them a comment is free text.
+    #[serde(default)]
+    pub comment_fields: Vec<FieldDefinition>,
 }
 
-fn default_automated() -> AutomatedPolicy {
-    AutomatedPolicy::Allow
+/// Keys read from submitted values besides field ids: `require_reporter`
+/// policies look for `reporter`.
+const RESERVED_KEYS: &[&str] = &["reporter"];
+
+impl GripeSchema {
+    /// Keys in `values` that are neither field ids nor reserved, sorted.
+    pub fn extra_keys<'a>(&self, values: &'a HashMap<String, String>) -> Vec<&'a str> {
+        let mut keys: Vec<&str> = values
+            .keys()
+            .map(|k| k.as_str())
+            .filter(|k| !RESERVED_KEYS.contains(k) && !self.fields.iter().any(|f| f.id == *k))
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
 }
 
+/// How a field's value becomes a label: a template such as `tool:{value}`,
+/// or a table from values to labels (`critical: priority:p0`).
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum LabelRule {
+    Template(String),
+    Map(BTreeMap<String, String>),
+}
+
+/// Applies when every field in `when` has the given value (ignoring case).
+/// Labels, assignees and projects add to the schema's; milestone and type
+/// replace them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RoutingRule {
+    pub when: BTreeMap<String, String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub assignees: Vec<String>,
+    #[serde(default)]
+    pub milestone: Option<String>,
+    #[serde(default)]
+    pub projects: Vec<String>,
+    #[serde(default, rename = "type")]
+    pub issue_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CodeOwnersConfig {
+    /// Field holding a path, or a component name used in `path`.
+    pub field: String,
+    /// Template turning the value into a path, e.g. `src/{value}/`.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingLabels {
+    /// File the issue without the labels the repo doesn't have.
+    #[default]
+    Drop,
+    /// Create the missing labels first, falling back to dropping them.
+    Create,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
-pub enum AutomatedPolicy {
+pub enum ExtraFields {
+    /// Refuse submissions with unknown keys, suggesting the field meant.
+    Reject,
+    /// Render them under an "Other" heading.
+    Append,
+    /// Leave them out of the body, as gripe always has.
+    #[default]
+    Ignore,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LabelDefinition {
+    /// Hex color, with or without a leading `#`.
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AutomatedMode {
+    #[default]
     Allow,
     Deny,
+    /// Accept automated gripes but file them somewhere for triage first.
+    Review,
+}
+
+/// Rules for non-interactive submissions. Written either as a bare mode
+/// (`automated: deny`) or as a table with the extra options.
+/// Unknown keys are rejected: a misspelled option must not silently leave
+/// the policy at `allow`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AutomatedPolicy {
+    #[serde(default)]
+    pub mode: AutomatedMode,
+    /// Maximum automated issues per day from the submitting account.
+    #[serde(default)]
+    pub rate_limit: Option<u32>,
+    /// Labels added to every automated issue.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// If non-empty, only these agent identifiers may submit.
+    #[serde(default)]
+    pub allowed_agents: Vec<String>,
+    #[serde(default)]
+    pub blocked_agents: Vec<String>,
+    /// Require a non-empty `reporter` value on automated submissions.
+    #[serde(default)]
+    pub require_reporter: bool,
+    /// Where `review` mode sends automated gripes.
+    #[serde(default)]
+    pub review: Option<ReviewTarget>,
+}
+
+/// Destination for automated gripes under `mode: review`: a dedicated triage
+/// repo, or a discussion category in the target repo. Exactly one is set.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReviewTarget {
+    #[serde(default)]
+    pub repo: Option<String>,
+    #[serde(default)]
+    pub discussion_category: Option<String>,
+}
+
+impl AutomatedPolicy {
+    pub fn with_mode(mode: AutomatedMode) -> Self {
+        AutomatedPolicy {
+            mode,
+            ..Default::default()
+        }
+    }
+}
+
+pub fn deserialize_policy<'de, D>(deserializer: D) -> Result<AutomatedPolicy, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Spec {
+        Mode(AutomatedMode),
+        Full(AutomatedPolicy),
+    }
+
+    let policy = match Spec::deserialize(deserializer)? {
+        Spec::Mode(mode) => AutomatedPolicy::with_mode(mode),
+        Spec::Full(policy) => policy,
+    };
+    if let Some(ReviewTarget {
+        repo: Some(_),
+        discussion_category: Some(_),
+    }) = policy.review
+    {
+        return Err(serde::de::Error::custom(
+            "automated.review takes either repo or discussion_category, not both",
+        ));
+    }
+    Ok(policy)
+}
+
+fn serialize_policy<S>(policy: &AutomatedPolicy, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    // Keep the short form when only the mode is set
+    if *policy == AutomatedPolicy::with_mode(policy.mode.clone()) {
+        policy.mode.serialize(serializer)
+    } else {
+        policy.serialize(serializer)
+    }
+}
+
+/// Custom layout for the issue body, given inline or as a path relative to
+/// gripe.yaml (`body_template: { file: body.md }`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BodyTemplate {
+    Inline(String),
+    File { file: String },
+}
+
+impl BodyTemplate {
+    pub fn source(&self) -> Result<String, String> {
+        match self {
+            BodyTemplate::Inline(src) => Ok(src.clone()),
+            BodyTemplate::File { file } => std::fs::read_to_string(file)
+                .map_err(|e| format!("Failed to read body template {}: {}", file, e)),
+        }
+    }
+}
+
+/// Secret redaction applied to field values and attachments before filing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Extra regexes to redact in addition to the built-in secret patterns.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+impl Default for RedactConfig {
+    fn default() -> Self {
+        RedactConfig {
+            enabled: true,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +285,9 @@ pub struct FieldDefinition {
     pub id: String,
     #[serde(default)]
     pub label: Option<String>,
+    /// Help text for the field, shown in exported JSON Schemas.
+    #[serde(default)]
+    pub description: Option<String>,
     #[serde(default = "default_field_type")]
     #[serde(rename = "type")]
     pub field_type: FieldType,
@@ -38,6 +297,14 @@ pub struct FieldDefinition {
     pub options: Vec<String>,
     #[serde(default)]
     pub default: Option<String>,
+    /// Regex that text values (and each `list` item) must match.
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Bounds for `number` fields.
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
 }
 
 fn default_field_type() -> FieldType {
@@ -50,6 +317,11 @@ pub enum FieldType {
     Input,
     Textarea,
     Select,
+    File,
+    Number,
+    Checkbox,
+    /// Several values, one per line.
+    List,
 }
 
 impl FieldDefinition {
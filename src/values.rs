@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::types::{FieldDefinition, FieldType, GripeSchema};
+
+/// Turn submitted values (JSON, or strings from the command line and prompts)
+/// into the text each field is rendered from, checking they suit the field.
+///
+/// Lists become one item per line, the form `{{#each}}` iterates over;
+/// checkboxes become `true` or `false`; objects become fenced JSON. Nulls are
+/// left out, as if the field had not been given.
+pub fn normalize(
+    schema: &GripeSchema,
+    raw: HashMap<String, Value>,
+) -> Result<HashMap<String, String>, String> {
+    let mut values = HashMap::new();
+    let mut errors = Vec::new();
+
+    for (key, value) in raw {
+        if value.is_null() {
+            continue;
+        }
+        let converted = match schema.fields.iter().find(|f| f.id == key) {
+            Some(field) => convert(field, value),
+            None => Ok(free_text(value)),
+        };
+        match converted {
+            Ok(text) => {
+                values.insert(key, text);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        errors.sort();
+        Err(errors.join("; "))
+    }
+}
+
+fn convert(field: &FieldDefinition, value: Value) -> Result<String, String> {
+    let text = convert_type(field, value)?;
+    if field.field_type != FieldType::Number {
+        check_value(field, &text)?;
+    }
+    Ok(text)
+}
+
+fn convert_type(field: &FieldDefinition, value: Value) -> Result<String, String> {
+    let label = field.display_label();
+    match (&field.field_type, value) {
+        (FieldType::Number, value) => {
+            let text =
+                scalar_text(&value).ok_or_else(|| format!("'{}' must be a number", label))?;
+            check_value(field, &text)?;
+            Ok(text.trim().to_string())
+        }
+        (FieldType::Checkbox, value) => scalar_text(&value)
+            .as_deref()
+            .and_then(parse_checkbox)
+            .map(|checked| checked.to_string())
+            .ok_or_else(|| format!("'{}' must be true or false", label)),
+        (FieldType::List, Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                scalar_text(item).ok_or_else(|| {
+                    format!("'{}' takes a list of strings, numbers or booleans", label)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|items| items.join("\n")),
+        (FieldType::List, value) => Ok(split_list(&scalar_text(&value).unwrap_or_default())),
+        (FieldType::Textarea, value) => Ok(free_text(value)),
+        // Structured detail for a text field, such as an error object
+        (FieldType::Input, value @ Value::Object(_)) => Ok(free_text(value)),
+        (_, value) => {
+            scalar_text(&value).ok_or_else(|| format!("'{}' takes a single value", label))
+        }
+    }
+}
+
+/// Check a value against the field's `pattern` (each item, for lists) and,
+/// for number fields, `min`/`max`. Empty values are left to the
+/// required-field check.
+pub fn check_value(field: &FieldDefinition, text: &str) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Ok(());
+    }
+    let label = field.display_label();
+
+    if let Some(pattern) = &field.pattern {
+        let re = Regex::new(pattern)
+            .map_err(|e| format!("'{}' has an invalid pattern: {}", label, e))?;
+        let items: Vec<&str> = match field.field_type {
+            FieldType::List => text.lines().collect(),
+            _ => vec![text],
+        };
+        if let Some(bad) = items.iter().find(|item| !re.is_match(item)) {
+            return Err(format!(
+                "'{}' must match `{}`, not '{}'",
+                label, pattern, bad
+            ));
+        }
+    }
+
+    match field.field_type {
+        FieldType::Number => check_number(field, text.trim()),
+        _ => Ok(()),
+    }
+}
+
+fn check_number(field: &FieldDefinition, text: &str) -> Result<(), String> {
+    let label = field.display_label();
+    let n: f64 = text
+        .parse()
+        .map_err(|_| format!("'{}' must be a number, not '{}'", label, text))?;
+    if let Some(min) = field.min.filter(|min| n < *min) {
+        return Err(format!("'{}' must be at least {}", label, min));
+    }
+    if let Some(max) = field.max.filter(|max| n > *max) {
+        return Err(format!("'{}' must be at most {}", label, max));
+    }
+    Ok(())
+}
+
+/// Read a checkbox value, accepting the usual spellings of yes and no.
+pub fn parse_checkbox(text: &str) -> Option<bool> {
+    match text.trim().to_lowercase().as_str() {
+        "true" | "yes" | "y" | "on" | "1" | "x" => Some(true),
+        "false" | "no" | "n" | "off" | "0" | "" => Some(false),
+        _ => None,
+    }
+}
+
+/// Items of a list given as one string, separated by commas or newlines.
+fn split_list(text: &str) -> String {
+    text.split(['\n', ','])
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Null => Some(String::new()),
+        Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+/// Text for fields that take anything: lists of scalars one per line,
+/// objects as fenced JSON.
+fn free_text(value: Value) -> String {
+    if let Some(text) = scalar_text(&value) {
+        return text;
+    }
+    if let Value::Array(items) = &value {
+        if let Some(items) = items.iter().map(scalar_text).collect::<Option<Vec<_>>>() {
+            return items.join("\n");
+        }
+    }
+    let json = serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string());
+    format!("```json\n{}\n```", json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> GripeSchema {
+        serde_yaml::from_str(
+            "fields:\n  - { id: count, type: number, min: 1, max: 10 }\n  - { id: agree, type: checkbox }\n  - { id: platforms, type: list }\n  - { id: details, type: textarea }\n  - { id: summary }\n",
+        )
+        .unwrap()
+    }
+
+    fn normalize_json(value: Value) -> Result<HashMap<String, String>, String> {
+        let raw = serde_json::from_value(value).unwrap();
+        normalize(&schema(), raw)
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let values = normalize_json(json!({
+            "count": 3,
+            "agree": true,
+            "platforms": ["linux", "mac"],
+            "details": { "os": "linux" },
+            "summary": null,
+        }))
+        .unwrap();
+        assert_eq!(values["count"], "3");
+        assert_eq!(values["agree"], "true");
+        assert_eq!(values["platforms"], "linux\nmac");
+        assert!(values["details"].starts_with("```json\n{\n  \"os\": \"linux\""));
+        assert!(!values.contains_key("summary"));
+    }
+
+    #[test]
+    fn test_strings_are_coerced() {
+        let values = normalize_json(json!({
+            "count": "10",
+            "agree": "yes",
+            "platforms": "linux, mac,",
+        }))
+        .unwrap();
+        assert_eq!(values["agree"], "true");
+        assert_eq!(values["platforms"], "linux\nmac");
+    }
+
+    #[test]
+    fn test_type_errors() {
+        let err = normalize_json(json!({
+            "count": 11,
+            "agree": "maybe",
+            "summary": ["a", "b"],
+        }))
+        .unwrap_err();
+        assert!(err.contains("'count' must be at most 10"));
+        assert!(err.contains("'agree' must be true or false"));
+        assert!(err.contains("'summary' takes a single value"));
+        assert!(normalize_json(json!({ "count": "lots" })).is_err());
+        for agree in [json!({ "yes": true }), json!([true])] {
+            let err = normalize_json(json!({ "agree": agree })).unwrap_err();
+            assert!(err.contains("'agree' must be true or false"));
+        }
+    }
+
+    #[test]
+    fn test_objects_in_text_fields_are_fenced() {
+        let values = normalize_json(json!({ "summary": { "code": 500 } })).unwrap();
+        assert_eq!(values["summary"], "```json\n{\n  \"code\": 500\n}\n```");
+    }
+
+    #[test]
+    fn test_pattern() {
+        let schema: GripeSchema = serde_yaml::from_str(
+            "fields:\n  - { id: version, pattern: '^\\d+\\.\\d+' }\n  - { id: tags, type: list, pattern: '^[a-z]+$' }\n",
+        )
+        .unwrap();
+        let check = |value: Value| normalize(&schema, serde_json::from_value(value).unwrap());
+        assert!(check(json!({ "version": "1.2.3", "tags": ["ui", "crash"] })).is_ok());
+        assert!(check(json!({ "version": "latest" }))
+            .unwrap_err()
+            .contains("must match"));
+        assert!(check(json!({ "tags": "ui, Crash" })).is_err());
+    }
+}